@@ -1,35 +1,131 @@
-use std::fs;
+use std::collections::VecDeque;
+use std::env;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader};
 
 const INPUT_FILE_NAME: &str = "input";
+// passing this instead of a file name reads the sweep from stdin
+const STDIN_SOURCE: &str = "-";
+
+type Depth = u64;
+
+// compares the sums of neighbouring windows of readings as they stream past
+struct SweepAnalyser {
+    window_size: usize,
+    // the readings that make up the current window, oldest first
+    window: VecDeque<Depth>,
+    num_increasing: usize,
+    num_decreasing: usize,
+    num_flat: usize,
+    current_run: usize,
+    longest_run: usize,
+    largest_increase: Option<i128>,
+}
 
 fn main() {
-    let numbers = get_numbers();
-    part_a(&numbers);
-    part_b(&numbers);
+    // `cargo run -- sweep.txt` reads another file, and `cargo run -- -` reads stdin
+    let source = env::args().nth(1).unwrap_or(INPUT_FILE_NAME.to_owned());
+    let reader: Box<dyn BufRead> = if source == STDIN_SOURCE {
+        Box::new(io::stdin().lock())
+    } else {
+        let file = File::open(&source).unwrap_or_else(|e| panic!("{}: {}", source, e));
+        Box::new(BufReader::new(file))
+    };
+    // the readings are only seen once, so both parts are worked out in the same pass
+    let mut part_a_analyser = SweepAnalyser::new(1);
+    let mut part_b_analyser = SweepAnalyser::new(3);
+    for reading in get_readings(reader, &source) {
+        part_a_analyser.push(reading);
+        part_b_analyser.push(reading);
+    }
+    part_a(&part_a_analyser);
+    part_b(&part_b_analyser);
 }
 
-fn get_numbers() -> Vec<u16> {
-    fs::read_to_string(INPUT_FILE_NAME)
-        .unwrap()
+fn get_readings<'a>(
+    reader: impl BufRead + 'a,
+    source: &'a str,
+) -> impl Iterator<Item = Depth> + 'a {
+    reader
         .lines()
-        .map(|x| x.parse::<u16>().unwrap())
-        .collect()
+        .enumerate()
+        .map(move |(line_idx, line)| {
+            let line = line.unwrap_or_else(|e| panic!("{} line {}: {}", source, line_idx + 1, e));
+            (line_idx, line)
+        })
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(move |(line_idx, line)| {
+            line.trim().parse::<Depth>().unwrap_or_else(|e| {
+                panic!(
+                    "{} line {}: {:?} isn't a depth: {}",
+                    source,
+                    line_idx + 1,
+                    line,
+                    e
+                )
+            })
+        })
 }
 
-fn part_a(numbers: &Vec<u16>) {
-    let num_increasing = numbers
-        .windows(2)
-        .filter(|x| x[0] < x[1])
-        .count();
-    println!("Total increasing in part A:\n{}", num_increasing);
+fn part_a(analyser: &SweepAnalyser) {
+    println!("Total increasing in part A:\n{}", analyser.num_increasing);
+    analyser.print_summary();
 }
 
-fn part_b(numbers: &Vec<u16>) {
-    let num_increasing = numbers
-        .windows(3)
-        .collect::<Vec<_>>()
-        .windows(2)
-        .filter(|x| x[0].iter().sum::<u16>() < x[1].iter().sum::<u16>())
-        .count();
-    println!("Total increasing in part B:\n{}", num_increasing);
+fn part_b(analyser: &SweepAnalyser) {
+    println!("Total increasing in part B:\n{}", analyser.num_increasing);
+    analyser.print_summary();
+}
+
+impl SweepAnalyser {
+    fn new(window_size: usize) -> SweepAnalyser {
+        assert!(window_size > 0, "the window size must be at least 1");
+        SweepAnalyser {
+            window_size,
+            window: VecDeque::with_capacity(window_size + 1),
+            num_increasing: 0,
+            num_decreasing: 0,
+            num_flat: 0,
+            current_run: 0,
+            longest_run: 0,
+            largest_increase: None,
+        }
+    }
+
+    fn push(&mut self, reading: Depth) {
+        self.window.push_back(reading);
+        if self.window.len() <= self.window_size {
+            return;
+        }
+        // neighbouring windows share all but one reading at each end, so the change in
+        // their sums is just the newest reading minus the one that dropped out
+        let dropped = self.window.pop_front().unwrap();
+        // widened so that even the largest depths can't overflow the difference
+        let change = reading as i128 - dropped as i128;
+        if change > 0 {
+            self.num_increasing += 1;
+            self.current_run += 1;
+            self.longest_run = self.longest_run.max(self.current_run);
+            self.largest_increase = Some(self.largest_increase.map_or(change, |x| x.max(change)));
+        } else {
+            if change < 0 {
+                self.num_decreasing += 1;
+            } else {
+                self.num_flat += 1;
+            }
+            self.current_run = 0;
+        }
+    }
+
+    fn print_summary(&self) {
+        println!(
+            "Window of {}: {} increasing, {} decreasing, {} flat",
+            self.window_size, self.num_increasing, self.num_decreasing, self.num_flat
+        );
+        println!("Longest run of increases: {}", self.longest_run);
+        match self.largest_increase {
+            Some(x) => println!("Largest single increase: {}", x),
+            None => println!("Largest single increase: none"),
+        }
+    }
 }
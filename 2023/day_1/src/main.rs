@@ -1,104 +1,282 @@
+use std::cmp::Reverse;
+use std::collections::{HashMap, VecDeque};
+use std::env;
 use std::fs;
 
 const INPUT_FILE_NAME: &str = "input.txt";
+const VOCABULARY_FILE_NAME: &str = "vocabularies.txt";
+// the number words part b uses when no vocabulary is picked on the command line
+const DEFAULT_VOCABULARY: &str = "english";
+// show every digit found on each line, along with where it starts
+const PRINT_RECOGNISED_DIGITS: bool = false;
 
-const WORDY_DIGITS: [(&'static str, u16); 19] = [
-    ("1", 1), 
-    ("2", 2), 
-    ("3", 3), 
-    ("4", 4), 
-    ("5", 5), 
-    ("6", 6), 
-    ("7", 7), 
-    ("8", 8), 
+const NUMERALS: [(&str, u16); 10] = [
+    ("1", 1),
+    ("2", 2),
+    ("3", 3),
+    ("4", 4),
+    ("5", 5),
+    ("6", 6),
+    ("7", 7),
+    ("8", 8),
     ("9", 9),
     ("0", 0),
-    ("one", 1), 
-    ("two", 2), 
-    ("three", 3), 
-    ("four", 4), 
-    ("five", 5), 
-    ("six", 6), 
-    ("seven", 7), 
-    ("eight", 8), 
-    ("nine", 9)
 ];
 
-fn main() {
-    part_a();
-    part_b();
+const NUMBER_WORDS: [(&str, u16); 9] = [
+    ("one", 1),
+    ("two", 2),
+    ("three", 3),
+    ("four", 4),
+    ("five", 5),
+    ("six", 6),
+    ("seven", 7),
+    ("eight", 8),
+    ("nine", 9),
+];
+
+#[derive(Default)]
+struct TrieNode {
+    children: HashMap<char, usize>,
+    // where to carry on from when the next character doesn't extend this node
+    fail: usize,
+    // the length in bytes and value of the word ending at this node, if any
+    word: Option<(usize, u16)>,
+    // the nearest node along the fail chain that ends a word
+    dictionary_link: Option<usize>,
+}
+
+// an Aho-Corasick automaton over the number words, so a line is read once and overlapping
+// words like "twone" give both digits
+struct DigitMatcher {
+    nodes: Vec<TrieNode>,
 }
 
-fn is_digit(x: &char) -> bool {
-    x.to_digit(10).is_some()    
+struct RecognisedDigit {
+    position: usize,
+    length: usize,
+    value: u16,
 }
 
-fn get_num_from_line(line: &str) -> u16 {
-    let first_digit = line.chars().find(|x| is_digit(x)).unwrap();
-    let last_digit = line.chars().rev().find(|x| is_digit(x)).unwrap();
-    format!("{}{}", first_digit, last_digit).parse::<u16>().unwrap()    
+fn main() {
+    let lines = get_lines();
+    // `cargo run -- french zero` counts French words and "zero" as digits in part b
+    let mut vocabulary_names: Vec<String> = env::args().skip(1).collect();
+    if vocabulary_names.is_empty() {
+        vocabulary_names.push(DEFAULT_VOCABULARY.to_owned());
+    }
+    part_a(&lines);
+    part_b(&lines, &get_vocabulary(&vocabulary_names));
 }
 
-fn get_numbers() -> Vec<u16> {
+fn get_lines() -> Vec<String> {
     fs::read_to_string(INPUT_FILE_NAME)
-        .unwrap()        
+        .unwrap()
         .lines()
-        .map(|x|  get_num_from_line(x))
+        .map(|x| x.to_string())
         .collect()
 }
 
-fn part_a() {
-    let numbers = get_numbers();
-    let total: u16 = numbers.iter().sum();
-    println!("{}", total);
+fn get_vocabulary(names: &[String]) -> Vec<(String, u16)> {
+    let mut file_vocabularies: Option<HashMap<String, Vec<(String, u16)>>> = None;
+    let mut vocabulary = Vec::new();
+    for name in names {
+        if name == DEFAULT_VOCABULARY {
+            vocabulary.extend(
+                NUMBER_WORDS
+                    .iter()
+                    .map(|&(word, value)| (word.to_owned(), value)),
+            );
+            continue;
+        }
+        // only go looking for the file once something other than english is asked for
+        let vocabularies = file_vocabularies.get_or_insert_with(read_vocabularies);
+        match vocabularies.get(name) {
+            Some(words) => vocabulary.extend(words.iter().cloned()),
+            None => {
+                let mut known: Vec<&String> = vocabularies.keys().collect();
+                known.sort();
+                panic!(
+                    "unknown vocabulary {}, expected {} or one of {:?}",
+                    name, DEFAULT_VOCABULARY, known
+                )
+            }
+        }
+    }
+    vocabulary
 }
 
-fn part_b() {
-    let numbers = get_numbers_including_from_words();
-    let total: u16 = numbers.iter().sum();
-    println!("{}", total);
+fn read_vocabularies() -> HashMap<String, Vec<(String, u16)>> {
+    // the vocabulary file is split into "[name]" sections of "word = value" lines, e.g.
+    //   [french]
+    //   un = 1
+    //   deux = 2
+    let contents = fs::read_to_string(VOCABULARY_FILE_NAME).unwrap();
+    let mut vocabularies: HashMap<String, Vec<(String, u16)>> = HashMap::new();
+    let mut current: Option<String> = None;
+    for (line_number, line) in contents.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        if let Some(name) = line.strip_prefix('[').and_then(|x| x.strip_suffix(']')) {
+            vocabularies.entry(name.trim().to_owned()).or_default();
+            current = Some(name.trim().to_owned());
+            continue;
+        }
+        let fail = |message: &str| -> ! {
+            panic!(
+                "{} line {}: {}",
+                VOCABULARY_FILE_NAME,
+                line_number + 1,
+                message
+            )
+        };
+        let name = current
+            .as_ref()
+            .unwrap_or_else(|| fail("expected a [name] before any words"));
+        let (word, value) = line
+            .split_once('=')
+            .unwrap_or_else(|| fail("expected word = value"));
+        let value = match value.trim().parse::<u16>() {
+            Ok(value) if value < 10 => value,
+            _ => fail("the value should be a single digit"),
+        };
+        if word.trim().is_empty() {
+            fail("the word is empty");
+        }
+        vocabularies
+            .get_mut(name)
+            .unwrap()
+            .push((word.trim().to_owned(), value));
+    }
+    vocabularies
 }
 
-fn optional_get_max(left: Option<(usize, u16)>, right: Option<(usize, u16)>) -> Option<(usize, u16)>
-{
-    match left {
-        None => right,
-        Some((l_idx, _l_val)) => {
-            match right {
-                None => left,
-                Some((r_idx, _r_val)) => if l_idx > r_idx {left} else {right}
-            }
+fn part_a(lines: &[String]) {
+    let vocabulary: Vec<(String, u16)> = NUMERALS
+        .iter()
+        .map(|&(word, value)| (word.to_owned(), value))
+        .collect();
+    let matcher = DigitMatcher::new(&vocabulary);
+    println!("{}", get_calibration_total(lines, &matcher));
+}
+
+fn part_b(lines: &[String], number_words: &[(String, u16)]) {
+    let vocabulary: Vec<(String, u16)> = NUMERALS
+        .iter()
+        .map(|&(word, value)| (word.to_owned(), value))
+        .chain(number_words.iter().cloned())
+        .collect();
+    let matcher = DigitMatcher::new(&vocabulary);
+    println!("{}", get_calibration_total(lines, &matcher));
+}
+
+fn get_calibration_total(lines: &[String], matcher: &DigitMatcher) -> u32 {
+    let mut total = 0;
+    for (line_idx, line) in lines.iter().enumerate() {
+        let digits = matcher.find_digits(line);
+        if PRINT_RECOGNISED_DIGITS {
+            let found: Vec<String> = digits
+                .iter()
+                .map(|x| format!("{}@{}", x.value, x.position))
+                .collect();
+            println!("{}: {}", line, found.join(" "));
+        }
+        match get_calibration_value(&digits) {
+            Some(value) => total += value as u32,
+            None => println!("Line {} has no digits: {}", line_idx + 1, line),
         }
     }
+    total
 }
 
-fn optional_get_min(left: Option<(usize, u16)>, right: Option<(usize, u16)>) -> Option<(usize, u16)>
-{    
-    match left {
-        None => right,
-        Some((l_idx, _l_val)) => {
-            match right {
-                None => left,
-                Some((r_idx, _r_val)) => if l_idx < r_idx {left} else {right}
-            }
+fn get_calibration_value(digits: &[RecognisedDigit]) -> Option<u16> {
+    // the first digit is the longest of the words starting earliest, which the sort puts
+    // first, and the last is the mirror of that: the longest of the words ending latest.
+    // that way "viii" reads as 8 both ways round rather than ending on the "i" inside it
+    let first = digits.first()?;
+    let last = digits
+        .iter()
+        .max_by_key(|x| (x.position + x.length, x.length))?;
+    Some(first.value * 10 + last.value)
+}
+
+fn follow(nodes: &[TrieNode], mut node: usize, ch: char) -> usize {
+    // falls back along the fail links until something can be extended by ch
+    loop {
+        if let Some(&next) = nodes[node].children.get(&ch) {
+            return next;
         }
+        if node == 0 {
+            return 0;
+        }
+        node = nodes[node].fail;
     }
 }
 
-fn get_num_from_line_maybe_from_word(line: &str) -> u16 {
-    let mut min_idx_val: Option<(usize, u16)> = None;
-    let mut max_idx_val: Option<(usize, u16)> = None;    
-    for (name, val) in WORDY_DIGITS {
-        min_idx_val = optional_get_min(min_idx_val, line.find(name).map(|idx| (idx, val)));
-        max_idx_val = optional_get_max(max_idx_val, line.rfind(name).map(|idx| (idx, val)));
+impl DigitMatcher {
+    fn new(vocabulary: &[(String, u16)]) -> DigitMatcher {
+        let mut nodes = vec![TrieNode::default()];
+        for (word, value) in vocabulary {
+            let mut node = 0;
+            for ch in word.chars() {
+                node = match nodes[node].children.get(&ch) {
+                    Some(&child) => child,
+                    None => {
+                        nodes.push(TrieNode::default());
+                        let child = nodes.len() - 1;
+                        nodes[node].children.insert(ch, child);
+                        child
+                    }
+                };
+            }
+            nodes[node].word = Some((word.len(), *value));
+        }
+
+        // breadth first, so a node's fail link is always set before its children need it
+        let mut queue: VecDeque<usize> = nodes[0].children.values().copied().collect();
+        while let Some(node) = queue.pop_front() {
+            let children: Vec<(char, usize)> = nodes[node]
+                .children
+                .iter()
+                .map(|(&ch, &child)| (ch, child))
+                .collect();
+            for (ch, child) in children {
+                let fail = follow(&nodes, nodes[node].fail, ch);
+                nodes[child].fail = fail;
+                nodes[child].dictionary_link = match nodes[fail].word {
+                    Some(_) => Some(fail),
+                    None => nodes[fail].dictionary_link,
+                };
+                queue.push_back(child);
+            }
+        }
+        DigitMatcher { nodes }
     }
-    format!("{}{}", min_idx_val.unwrap().1, max_idx_val.unwrap().1).parse::<u16>().unwrap()
-}
 
-fn get_numbers_including_from_words() -> Vec<u16> {
-    fs::read_to_string(INPUT_FILE_NAME)
-        .unwrap()        
-        .lines()
-        .map(|x|  get_num_from_line_maybe_from_word(x))
-        .collect()
-}
\ No newline at end of file
+    fn find_digits(&self, line: &str) -> Vec<RecognisedDigit> {
+        let mut digits = Vec::new();
+        let mut node = 0;
+        for (idx, ch) in line.char_indices() {
+            node = follow(&self.nodes, node, ch);
+            let end = idx + ch.len_utf8();
+            let mut output = match self.nodes[node].word {
+                Some(_) => Some(node),
+                None => self.nodes[node].dictionary_link,
+            };
+            while let Some(matched) = output {
+                let (length, value) = self.nodes[matched].word.unwrap();
+                digits.push(RecognisedDigit {
+                    position: end - length,
+                    length,
+                    value,
+                });
+                output = self.nodes[matched].dictionary_link;
+            }
+        }
+        // matches are found in order of where they end, but we want them in reading order
+        digits.sort_by_key(|x| (x.position, Reverse(x.length)));
+        digits
+    }
+}
@@ -1,24 +1,115 @@
 extern crate itertools;
 use itertools::Itertools;
+use std::cmp::Ordering;
+use std::fmt;
 use std::fs;
+use std::iter::Sum;
+use std::ops::Add;
 
 const INPUT_FILE_NAME: &str = "input.txt";
 const PART_1_EXPANSION_RATE: u64 = 2;
 const PART_2_EXPANSION_RATE: u64 = 1000000;
 
+#[derive(Debug, Clone, Copy)]
+enum DistanceMetric {
+    Manhattan,
+    Chebyshev,
+    Euclidean,
+}
+
+// grid metrics give whole numbers, which are kept exact however big the totals get, and
+// only a straight line distance needs to be a float
+#[derive(Debug, Clone, Copy)]
+enum Distance {
+    Exact(u64),
+    Approximate(f64),
+}
+
+impl DistanceMetric {
+    fn distance(&self, a: &(u64, u64), b: &(u64, u64)) -> Distance {
+        let row_diff = a.0.abs_diff(b.0);
+        let col_diff = a.1.abs_diff(b.1);
+        match self {
+            DistanceMetric::Manhattan => Distance::Exact(row_diff + col_diff),
+            DistanceMetric::Chebyshev => Distance::Exact(row_diff.max(col_diff)),
+            DistanceMetric::Euclidean => {
+                Distance::Approximate((row_diff as f64).hypot(col_diff as f64))
+            }
+        }
+    }
+}
+
+impl Distance {
+    fn as_f64(&self) -> f64 {
+        match self {
+            Distance::Exact(x) => *x as f64,
+            Distance::Approximate(x) => *x,
+        }
+    }
+
+    fn total_cmp(&self, other: &Distance) -> Ordering {
+        match (self, other) {
+            (Distance::Exact(a), Distance::Exact(b)) => a.cmp(b),
+            _ => self.as_f64().total_cmp(&other.as_f64()),
+        }
+    }
+}
+
+impl Add for Distance {
+    type Output = Distance;
+
+    fn add(self, other: Distance) -> Distance {
+        match (self, other) {
+            (Distance::Exact(a), Distance::Exact(b)) => Distance::Exact(a + b),
+            _ => Distance::Approximate(self.as_f64() + other.as_f64()),
+        }
+    }
+}
+
+impl Sum for Distance {
+    fn sum<I: Iterator<Item = Distance>>(iter: I) -> Distance {
+        iter.fold(Distance::Exact(0), |total, x| total + x)
+    }
+}
+
+impl fmt::Display for Distance {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Distance::Exact(x) => write!(f, "{}", x),
+            Distance::Approximate(x) => write!(f, "{}", x),
+        }
+    }
+}
+
+#[derive(Debug)]
+struct Neighbours {
+    galaxy: (u64, u64),
+    nearest: (u64, u64),
+    nearest_distance: Distance,
+    farthest: (u64, u64),
+    farthest_distance: Distance,
+}
+
 fn main() {
     let data = get_data();
-    calculate_total_distance(&data, PART_1_EXPANSION_RATE);
-    calculate_total_distance(&data, PART_2_EXPANSION_RATE);
+    for metric in [
+        DistanceMetric::Manhattan,
+        DistanceMetric::Chebyshev,
+        DistanceMetric::Euclidean,
+    ] {
+        calculate_total_distance(&data, PART_1_EXPANSION_RATE, metric);
+        calculate_total_distance(&data, PART_2_EXPANSION_RATE, metric);
+    }
+    report_neighbours(&data, PART_1_EXPANSION_RATE, DistanceMetric::Manhattan);
 }
 
-fn get_blank_column_indexes(data: &Vec<Vec<char>>) -> Vec<usize> {
+fn get_blank_column_indexes(data: &[Vec<char>]) -> Vec<usize> {
     (0..data.len())
         .filter(|idx| data.iter().all(|line| line[*idx] == '.'))
         .collect()
 }
 
-fn expand_space(data: &Vec<Vec<char>>) -> Vec<Vec<char>> {
+fn expand_space(data: &[Vec<char>]) -> Vec<Vec<char>> {
     // replace any complete row or col with a row/col of '$' characters
     let all_blank_column_indexes = get_blank_column_indexes(data);
     data.iter()
@@ -49,7 +140,7 @@ fn get_data() -> Vec<Vec<char>> {
         .collect()
 }
 
-fn get_all_galaxy_coords(raw_data: &Vec<Vec<char>>, expansion_rate: u64) -> Vec<(u64, u64)> {
+fn get_all_galaxy_coords(raw_data: &[Vec<char>], expansion_rate: u64) -> Vec<(u64, u64)> {
     // this is hard to write as idiomatic rust because of the need to jump the index forward
     // by leaps when hitting an expansion character.  Possible with a combination of folds
     // but it looks messy compared to the imperative version that increments the coords below
@@ -78,24 +169,81 @@ fn get_all_galaxy_coords(raw_data: &Vec<Vec<char>>, expansion_rate: u64) -> Vec<
     coords
 }
 
-fn get_distances_between_all_possible_pairs_of_galaxies(coords: &Vec<(u64, u64)>) -> Vec<u64> {
+fn get_distances_between_all_possible_pairs_of_galaxies(
+    coords: &[(u64, u64)],
+    metric: DistanceMetric,
+) -> Vec<Distance> {
     coords
         .iter()
         .combinations(2)
-        .map(|x| {
-            x.first().unwrap().0.abs_diff(x.last().unwrap().0)
-                + x.first().unwrap().1.abs_diff(x.last().unwrap().1)
+        .map(|x| metric.distance(x.first().unwrap(), x.last().unwrap()))
+        .collect()
+}
+
+fn get_nearest_and_farthest_neighbours(
+    coords: &[(u64, u64)],
+    metric: DistanceMetric,
+) -> Vec<Neighbours> {
+    // a galaxy on its own has no neighbours, so it is left out of the result
+    coords
+        .iter()
+        .enumerate()
+        .filter_map(|(idx, galaxy)| {
+            let others = coords
+                .iter()
+                .enumerate()
+                .filter(|(other_idx, _)| *other_idx != idx)
+                .map(|(_, other)| (*other, metric.distance(galaxy, other)));
+            let nearest = others.clone().min_by(|a, b| a.1.total_cmp(&b.1))?;
+            let farthest = others.max_by(|a, b| a.1.total_cmp(&b.1))?;
+            Some(Neighbours {
+                galaxy: *galaxy,
+                nearest: nearest.0,
+                nearest_distance: nearest.1,
+                farthest: farthest.0,
+                farthest_distance: farthest.1,
+            })
         })
         .collect()
 }
 
-fn calculate_total_distance(raw_data: &Vec<Vec<char>>, expansion_rate: u64) {
+fn calculate_total_distance(raw_data: &[Vec<char>], expansion_rate: u64, metric: DistanceMetric) {
     let coords = get_all_galaxy_coords(raw_data, expansion_rate);
     println!(
-        "Total distance between all galaxy pairs with expansion rate of {}: {}",
+        "Total {:?} distance between all galaxy pairs with expansion rate of {}: {}",
+        metric,
         expansion_rate,
-        get_distances_between_all_possible_pairs_of_galaxies(&coords)
+        get_distances_between_all_possible_pairs_of_galaxies(&coords, metric)
             .iter()
-            .sum::<u64>()
+            .copied()
+            .sum::<Distance>()
     );
 }
+
+fn report_neighbours(raw_data: &[Vec<char>], expansion_rate: u64, metric: DistanceMetric) {
+    let coords = get_all_galaxy_coords(raw_data, expansion_rate);
+    let neighbours = get_nearest_and_farthest_neighbours(&coords, metric);
+    let closest = neighbours
+        .iter()
+        .min_by(|a, b| a.nearest_distance.total_cmp(&b.nearest_distance));
+    let loneliest = neighbours
+        .iter()
+        .max_by(|a, b| a.nearest_distance.total_cmp(&b.nearest_distance));
+    let widest = neighbours
+        .iter()
+        .max_by(|a, b| a.farthest_distance.total_cmp(&b.farthest_distance));
+    if let (Some(closest), Some(loneliest), Some(widest)) = (closest, loneliest, widest) {
+        println!(
+            "Closest galaxies ({:?} with expansion rate of {}): {:?} and {:?}, {} apart",
+            metric, expansion_rate, closest.galaxy, closest.nearest, closest.nearest_distance
+        );
+        println!(
+            "Loneliest galaxy: {:?}, nearest neighbour {:?} is {} away",
+            loneliest.galaxy, loneliest.nearest, loneliest.nearest_distance
+        );
+        println!(
+            "Farthest apart galaxies: {:?} and {:?}, {} apart",
+            widest.galaxy, widest.farthest, widest.farthest_distance
+        );
+    }
+}
@@ -1,13 +1,18 @@
-use std::collections::HashMap;
+use std::collections::BTreeMap;
 use std::fs;
 
 const INPUT_FILE_NAME: &str = "input.txt";
+const PART_ONE_BAG: [(&str, u16); 3] = [("red", 12), ("green", 13), ("blue", 14)];
+// explain which draw ruled out each impossible game in part one
+const PRINT_IMPOSSIBLE_GAMES: bool = false;
+// answer questions about which bags would make the games possible
+const PRINT_BAG_QUERIES: bool = false;
+const BAG_QUERY_TOTAL_CUBES: u16 = 39;
 
 #[derive(Debug)]
 struct Sample {
-    n_red: u16,
-    n_green: u16,
-    n_blue: u16,
+    // colours that weren't drawn in a sample are simply missing, which counts as 0
+    counts: BTreeMap<String, u16>,
 }
 
 #[derive(Debug)]
@@ -16,48 +21,211 @@ struct Game {
     samples: Vec<Sample>,
 }
 
+// the colours we expect cubes to come in
+struct ColourSet {
+    colours: Vec<String>,
+}
+
+// how many cubes of each colour are in the bag, where a missing colour means none
+struct BagSpec {
+    limits: BTreeMap<String, u16>,
+}
+
+#[derive(Debug)]
+struct Violation {
+    sample_index: usize,
+    colour: String,
+    drawn: u16,
+    available: u16,
+}
+
 fn main() {
     let games = get_games();
-    part_one(&games);
-    part_two(&games);
+    let colours = ColourSet::standard();
+    for colour in colours.unexpected_colours(&games) {
+        println!(
+            "Warning: the input has {} cubes, which isn't a known colour",
+            colour
+        );
+    }
+    part_one(&games, &BagSpec::from_pairs(&PART_ONE_BAG));
+    part_two(&games, &colours);
+    if PRINT_BAG_QUERIES {
+        print_bag_queries(&games, &colours, BAG_QUERY_TOTAL_CUBES);
+    }
 }
 
 fn get_games() -> Vec<Game> {
     fs::read_to_string(INPUT_FILE_NAME)
         .unwrap()
         .lines()
-        .map(|line| get_game_from_line(line))
+        .map(get_game_from_line)
         .collect()
 }
 
-fn part_one(games: &Vec<Game>) {
+fn part_one(games: &[Game], bag: &BagSpec) {
     println!("Part One");
     let id_total: u16 = games
         .iter()
         .filter_map(|x| {
-            if x.is_valid(12, 13, 14) {
-                Some(x.id)
-            } else {
-                None
+            let violations = x.violations(bag);
+            if violations.is_empty() {
+                return Some(x.id);
+            }
+            if PRINT_IMPOSSIBLE_GAMES {
+                for violation in violations {
+                    println!(
+                        "Game {} is impossible: sample {} drew {} {} but the bag only has {}",
+                        x.id,
+                        violation.sample_index + 1,
+                        violation.drawn,
+                        violation.colour,
+                        violation.available
+                    );
+                }
             }
+            None
         })
         .sum();
     println!("Total: {}", id_total);
 }
 
-fn part_two(games: &Vec<Game>) {
+fn part_two(games: &[Game], colours: &ColourSet) {
     println!("Part Two");
-    let power_total: u32 = games.iter().map(|x| x.min_valid_power() as u32).sum();
+    let power_total: u32 = games.iter().map(|x| x.min_valid_power(colours)).sum();
     println!("Total: {}", power_total);
 }
 
+fn print_bag_queries(games: &[Game], colours: &ColourSet, total_cubes: u16) {
+    println!("Bag Queries");
+    let smallest = get_smallest_bag_for_all_games(games, colours);
+    println!(
+        "Smallest bag making every game possible: {} ({} cubes)",
+        smallest.describe(colours),
+        smallest.total()
+    );
+    let (num_games, bag) = get_max_possible_games(games, colours, total_cubes);
+    println!(
+        "Most games possible with {} cubes: {} using {}",
+        total_cubes,
+        num_games,
+        bag.describe(colours)
+    );
+    for (colour, histogram) in get_colour_histograms(games, colours) {
+        // how many samples drew each number of cubes of this colour
+        let counts: Vec<String> = histogram
+            .iter()
+            .map(|(drawn, samples)| format!("{}x{}", drawn, samples))
+            .collect();
+        println!("{}: {}", colour, counts.join(" "));
+    }
+}
+
+fn get_smallest_bag_for_all_games(games: &[Game], colours: &ColourSet) -> BagSpec {
+    // every game needs at least its own minimal bag, so take the largest need per colour
+    BagSpec {
+        limits: colours
+            .colours
+            .iter()
+            .map(|colour| {
+                let limit = games.iter().map(|x| x.min_count(colour)).max().unwrap_or(0);
+                (colour.clone(), limit)
+            })
+            .collect(),
+    }
+}
+
+fn get_max_possible_games(
+    games: &[Game],
+    colours: &ColourSet,
+    total_cubes: u16,
+) -> (usize, BagSpec) {
+    // a game is possible exactly when the bag covers its minimal bag, so the only per colour
+    // counts worth trying are the ones some game needs, with whatever is left over going to
+    // the last colour
+    let min_bags: Vec<Vec<u16>> = games
+        .iter()
+        .map(|game| {
+            colours
+                .colours
+                .iter()
+                .map(|colour| game.min_count(colour))
+                .collect()
+        })
+        .collect();
+    let mut best = (0, vec![0; colours.colours.len()]);
+    search_bags(
+        &min_bags,
+        colours.colours.len(),
+        total_cubes,
+        &mut Vec::new(),
+        &mut best,
+    );
+    let bag = BagSpec {
+        limits: colours.colours.iter().cloned().zip(best.1).collect(),
+    };
+    (best.0, bag)
+}
+
+fn search_bags(
+    min_bags: &[Vec<u16>],
+    num_colours: usize,
+    remaining: u16,
+    chosen: &mut Vec<u16>,
+    best: &mut (usize, Vec<u16>),
+) {
+    let colour_index = chosen.len();
+    if colour_index == num_colours {
+        let num_possible = min_bags
+            .iter()
+            .filter(|min_bag| min_bag.iter().zip(chosen.iter()).all(|(a, b)| a <= b))
+            .count();
+        if num_possible > best.0 {
+            *best = (num_possible, chosen.clone());
+        }
+        return;
+    }
+    let candidates: Vec<u16> = if colour_index + 1 == num_colours {
+        vec![remaining]
+    } else {
+        let mut candidates: Vec<u16> = min_bags
+            .iter()
+            .map(|x| x[colour_index])
+            .chain([0])
+            .filter(|&x| x <= remaining)
+            .collect();
+        candidates.sort();
+        candidates.dedup();
+        candidates
+    };
+    for candidate in candidates {
+        chosen.push(candidate);
+        search_bags(min_bags, num_colours, remaining - candidate, chosen, best);
+        chosen.pop();
+    }
+}
+
+fn get_colour_histograms(
+    games: &[Game],
+    colours: &ColourSet,
+) -> BTreeMap<String, BTreeMap<u16, usize>> {
+    let mut histograms: BTreeMap<String, BTreeMap<u16, usize>> = BTreeMap::new();
+    for colour in &colours.colours {
+        let histogram = histograms.entry(colour.clone()).or_default();
+        for sample in games.iter().flat_map(|x| &x.samples) {
+            *histogram.entry(sample.count(colour)).or_default() += 1;
+        }
+    }
+    histograms
+}
+
 fn get_game_from_line(line: &str) -> Game {
     // the game ID is everything between "Game" and ":"
-    let colon_idx = line.find(":").unwrap();
+    let colon_idx = line.find(':').unwrap();
     let id = line[5..colon_idx].parse::<u16>().unwrap();
-    let samples: Vec<Sample> = (&line[colon_idx + 1..])
-        .split(";")
-        .map(|sample_str| get_sample_from_slice(sample_str))
+    let samples: Vec<Sample> = line[colon_idx + 1..]
+        .split(';')
+        .map(get_sample_from_slice)
         .collect();
     Game { id, samples }
 }
@@ -65,32 +233,100 @@ fn get_game_from_line(line: &str) -> Game {
 fn get_sample_from_slice(slice: &str) -> Sample {
     // accepts a string slice representing the sample, such as "5 red, 1 green"
     // parses this and returns a Sample
-    let n_samples_map: HashMap<&str, u16> = slice
-        .split(",")
-        .map(|x| x.trim())
-        .map(|slice| {
-            let (a, b) = slice.split_at(slice.find(" ").unwrap());
-            (b.trim(), a.trim().parse::<u16>().unwrap())
-        })
-        .collect();
-    Sample {
-        n_red: n_samples_map.get("red").map(|x| *x).unwrap_or(0),
-        n_green: n_samples_map.get("green").map(|x| *x).unwrap_or(0),
-        n_blue: n_samples_map.get("blue").map(|x| *x).unwrap_or(0),
+    let mut counts: BTreeMap<String, u16> = BTreeMap::new();
+    for draw in slice.split(',').map(|x| x.trim()) {
+        let (a, b) = draw.split_at(draw.find(' ').unwrap());
+        // the same colour twice in one sample just means that many more cubes
+        *counts.entry(b.trim().to_owned()).or_default() += a.trim().parse::<u16>().unwrap();
+    }
+    Sample { counts }
+}
+
+impl Sample {
+    fn count(&self, colour: &str) -> u16 {
+        self.counts.get(colour).copied().unwrap_or(0)
+    }
+}
+
+impl ColourSet {
+    fn standard() -> ColourSet {
+        ColourSet {
+            colours: ["red", "green", "blue"].map(|x| x.to_owned()).to_vec(),
+        }
+    }
+
+    fn unexpected_colours(&self, games: &[Game]) -> Vec<String> {
+        let mut unexpected: Vec<String> = games
+            .iter()
+            .flat_map(|game| &game.samples)
+            .flat_map(|sample| sample.counts.keys())
+            .filter(|colour| !self.colours.contains(colour))
+            .cloned()
+            .collect();
+        unexpected.sort();
+        unexpected.dedup();
+        unexpected
+    }
+}
+
+impl BagSpec {
+    fn from_pairs(pairs: &[(&str, u16)]) -> BagSpec {
+        BagSpec {
+            limits: pairs
+                .iter()
+                .map(|(colour, limit)| (colour.to_string(), *limit))
+                .collect(),
+        }
+    }
+
+    fn limit(&self, colour: &str) -> u16 {
+        self.limits.get(colour).copied().unwrap_or(0)
+    }
+
+    fn total(&self) -> u32 {
+        self.limits.values().map(|&x| x as u32).sum()
+    }
+
+    fn describe(&self, colours: &ColourSet) -> String {
+        colours
+            .colours
+            .iter()
+            .map(|colour| format!("{} {}", self.limit(colour), colour))
+            .collect::<Vec<String>>()
+            .join(", ")
     }
 }
 
 impl Game {
-    fn is_valid(&self, max_red: u16, max_green: u16, max_blue: u16) -> bool {
-        self.samples.iter().all(|sample| {
-            sample.n_red <= max_red && sample.n_green <= max_green && sample.n_blue <= max_blue
-        })
+    fn violations(&self, bag: &BagSpec) -> Vec<Violation> {
+        self.samples
+            .iter()
+            .enumerate()
+            .flat_map(|(sample_index, sample)| {
+                sample
+                    .counts
+                    .iter()
+                    .filter(|(colour, &drawn)| drawn > bag.limit(colour))
+                    .map(move |(colour, &drawn)| Violation {
+                        sample_index,
+                        colour: colour.clone(),
+                        drawn,
+                        available: bag.limit(colour),
+                    })
+            })
+            .collect()
+    }
+
+    // the fewest cubes of a colour the bag could have held for this game
+    fn min_count(&self, colour: &str) -> u16 {
+        self.samples.iter().map(|x| x.count(colour)).max().unwrap()
     }
 
-    fn min_valid_power(&self) -> u16 {
-        let max_red = self.samples.iter().map(|x| x.n_red).max().unwrap();
-        let max_green = self.samples.iter().map(|x| x.n_green).max().unwrap();
-        let max_blue = self.samples.iter().map(|x| x.n_blue).max().unwrap();
-        max_red * max_green * max_blue
+    fn min_valid_power(&self, colours: &ColourSet) -> u32 {
+        colours
+            .colours
+            .iter()
+            .map(|colour| self.min_count(colour) as u32)
+            .product()
     }
 }
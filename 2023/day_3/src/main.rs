@@ -1,39 +1,213 @@
 use std::collections::HashMap;
+use std::env;
 use std::fs;
 
 const INPUT_FILE_NAME: &str = "input.txt";
 
+const GEAR_SYMBOL: char = '*';
+// a gear is a gear symbol next to exactly this many part numbers
+const GEAR_ARITY: usize = 2;
+// treat a '-' directly in front of a number as its sign rather than as a symbol
+const ALLOW_NEGATIVE_NUMBERS: bool = false;
+// `cargo run -- --print` prints the schematic with parts, non-parts and gears coloured in
+const PRINT_FLAG: &str = "--print";
+// `cargo run -- --html` writes the same again as a web page, which copes better with a full
+// size input
+const HTML_FLAG: &str = "--html";
+const HTML_FILE_NAME: &str = "schematic.html";
+
+#[derive(Clone, Copy, PartialEq)]
+enum CellStyle {
+    Plain,
+    Part,
+    NonPart,
+    Gear,
+}
+
 struct CartesianNumber {
-    value: u32,
+    value: i64,
+    // exactly as written in the schematic, including any sign or leading zeros
+    raw: String,
     row_num: usize,
     column_num: usize,
+    width: usize,
+}
+
+// every symbol in the schematic, and which numbers each one is next to
+struct SymbolIndex {
+    symbols: HashMap<(usize, usize), char>,
+    // indexed the same as the numbers the index was built from
+    symbols_by_number: Vec<Vec<(usize, usize)>>,
+    numbers_by_symbol: HashMap<(usize, usize), Vec<usize>>,
 }
 
 fn main() {
+    let mut print_schematic = false;
+    let mut write_html = false;
+    for arg in env::args().skip(1) {
+        match arg.as_str() {
+            PRINT_FLAG => print_schematic = true,
+            HTML_FLAG => write_html = true,
+            other => panic!(
+                "unknown option {}, expected {} or {}",
+                other, PRINT_FLAG, HTML_FLAG
+            ),
+        }
+    }
     let grid = get_grid();
     let cartesian_numbers = get_cartesian_numbers(&grid);
-    let engine_parts: Vec<u32> = cartesian_numbers
+    let symbol_index = SymbolIndex::new(&grid, &cartesian_numbers);
+    let is_engine_part: Vec<bool> = (0..cartesian_numbers.len())
+        .map(|number_index| symbol_index.is_engine_part(number_index))
+        .collect();
+    let engine_parts: Vec<i64> = cartesian_numbers
         .iter()
-        .filter(|x| x.is_engine_part(&grid))
-        .map(|x| x.value).collect();
-
-    println!("Part 1: {}", engine_parts.into_iter().sum::<u32>());
-    // for part 2, we need to map specific asterisk coords to vector of engine parts
-    let mut parts_by_asterisk_coords: HashMap<(usize, usize), Vec<u32>> = HashMap::new();
-    cartesian_numbers.iter().for_each(|cartesian_number| {
-        if let Some(coords) = cartesian_number.adjacent_asterisk_coords(&grid) {
-            parts_by_asterisk_coords.entry(coords).or_default().push(cartesian_number.value)
+        .zip(&is_engine_part)
+        .filter(|(_, &is_part)| is_part)
+        .map(|(x, _)| x.value)
+        .collect();
+
+    println!("Part 1: {}", engine_parts.into_iter().sum::<i64>());
+    for kind in symbol_index.symbol_kinds() {
+        let symbols = symbol_index.symbols_of_kind(kind);
+        let num_parts: usize = symbols
+            .iter()
+            .map(|coords| symbol_index.numbers_adjacent_to(coords).len())
+            .sum();
+        println!(
+            "{} symbols: {}, adjacent parts: {}",
+            kind,
+            symbols.len(),
+            num_parts
+        );
+    }
+    let gears = symbol_index.gear_ratios(&cartesian_numbers, GEAR_SYMBOL, GEAR_ARITY);
+    let gear_ratios: i64 = gears.iter().map(|(_, ratio)| ratio).sum();
+    println!("Part 2: {}", gear_ratios);
+
+    if print_schematic || write_html {
+        let styles = get_cell_styles(&grid, &cartesian_numbers, &is_engine_part, &gears);
+        if print_schematic {
+            print_highlighted_schematic(&grid, &styles, &gears);
         }
-    });
-    let gear_ratios: u32 = parts_by_asterisk_coords.values().map(
-        |parts| if parts.len() == 2 {
-            parts.iter().product::<u32>()
+        if write_html {
+            fs::write(HTML_FILE_NAME, get_highlighted_html(&grid, &styles, &gears)).unwrap();
+            println!("Wrote highlighted schematic to {}", HTML_FILE_NAME);
+        }
+    }
+}
+
+fn get_cell_styles(
+    grid: &[Vec<char>],
+    cartesian_numbers: &[CartesianNumber],
+    is_engine_part: &[bool],
+    gears: &[((usize, usize), i64)],
+) -> Vec<Vec<CellStyle>> {
+    let mut styles: Vec<Vec<CellStyle>> = grid
+        .iter()
+        .map(|row| vec![CellStyle::Plain; row.len()])
+        .collect();
+    for (cartesian_number, &is_part) in cartesian_numbers.iter().zip(is_engine_part) {
+        let style = if is_part {
+            CellStyle::Part
         } else {
-            0
+            CellStyle::NonPart
+        };
+        let start = cartesian_number.column_num;
+        styles[cartesian_number.row_num][start..start + cartesian_number.width].fill(style);
+    }
+    for &((row_num, column_num), _) in gears {
+        styles[row_num][column_num] = CellStyle::Gear;
+    }
+    styles
+}
+
+fn get_style_runs(row: &[char], row_styles: &[CellStyle]) -> Vec<(CellStyle, usize, String)> {
+    // group neighbouring cells with the same style so that a whole number is wrapped in
+    // one colour change, but keep gears apart since each one has its own ratio
+    let mut runs: Vec<(CellStyle, usize, String)> = Vec::new();
+    for (column_num, (&cell, &style)) in row.iter().zip(row_styles).enumerate() {
+        match runs.last_mut() {
+            Some((last_style, _, text)) if *last_style == style && style != CellStyle::Gear => {
+                text.push(cell)
+            }
+            _ => runs.push((style, column_num, cell.to_string())),
         }
-    ).sum();    
-    println!("Part 2: {}", gear_ratios);
+    }
+    runs
+}
+
+fn print_highlighted_schematic(
+    grid: &[Vec<char>],
+    styles: &[Vec<CellStyle>],
+    gears: &[((usize, usize), i64)],
+) {
+    for (row, row_styles) in grid.iter().zip(styles) {
+        let line: String = get_style_runs(row, row_styles)
+            .into_iter()
+            .map(|(style, _, text)| match style {
+                CellStyle::Plain => text,
+                CellStyle::Part => format!("\x1b[32m{}\x1b[0m", text),
+                CellStyle::NonPart => format!("\x1b[31m{}\x1b[0m", text),
+                CellStyle::Gear => format!("\x1b[1;30;43m{}\x1b[0m", text),
+            })
+            .collect();
+        println!("{}", line);
+    }
+    // the ratios don't fit in the grid, so list them underneath by position
+    for ((row_num, column_num), ratio) in gears {
+        println!(
+            "Gear at row {}, column {}: ratio {}",
+            row_num + 1,
+            column_num + 1,
+            ratio
+        );
+    }
+}
 
+fn get_highlighted_html(
+    grid: &[Vec<char>],
+    styles: &[Vec<CellStyle>],
+    gears: &[((usize, usize), i64)],
+) -> String {
+    let ratios: HashMap<(usize, usize), i64> = gears.iter().copied().collect();
+    let mut html = String::from(
+        "<!DOCTYPE html>\n<html>\n<head>\n<style>\n\
+         body { background: #1e1e1e; color: #808080; font-family: monospace; }\n\
+         .part { color: #4ec94e; }\n\
+         .non-part { color: #f05050; }\n\
+         .gear { background: #e0c020; color: #000000; font-weight: bold; }\n\
+         </style>\n</head>\n<body>\n<pre>\n",
+    );
+    for (row_num, (row, row_styles)) in grid.iter().zip(styles).enumerate() {
+        for (style, column_num, text) in get_style_runs(row, row_styles) {
+            let text = text
+                .replace('&', "&amp;")
+                .replace('<', "&lt;")
+                .replace('>', "&gt;");
+            match style {
+                CellStyle::Plain => html.push_str(&text),
+                CellStyle::Part => html.push_str(&format!("<span class=\"part\">{}</span>", text)),
+                CellStyle::NonPart => {
+                    html.push_str(&format!("<span class=\"non-part\">{}</span>", text))
+                }
+                // hovering over a gear shows its ratio
+                CellStyle::Gear => html.push_str(&format!(
+                    "<span class=\"gear\" title=\"ratio {}\">{}</span>",
+                    ratios[&(row_num, column_num)],
+                    text
+                )),
+            }
+        }
+        html.push('\n');
+    }
+    html.push_str("</pre>\n</body>\n</html>\n");
+    html
+}
+
+fn is_symbol(cell: &char) -> bool {
+    // letters or stray whitespace in a schematic aren't symbols, only punctuation is
+    cell.is_ascii_punctuation() && cell != &'.'
 }
 
 fn get_grid() -> Vec<Vec<char>> {
@@ -44,40 +218,43 @@ fn get_grid() -> Vec<Vec<char>> {
         .collect()
 }
 
-fn get_cartesian_numbers(grid: &Vec<Vec<char>>) -> Vec<CartesianNumber> {
+fn get_cartesian_numbers(grid: &[Vec<char>]) -> Vec<CartesianNumber> {
+    grid.iter()
+        .enumerate()
+        .flat_map(|(row_num, row)| tokenize_row(row, row_num, ALLOW_NEGATIVE_NUMBERS))
+        .collect()
+}
+
+fn tokenize_row(row: &[char], row_num: usize, allow_negative: bool) -> Vec<CartesianNumber> {
     let mut cartesian_numbers: Vec<CartesianNumber> = Vec::new();
-    for (row_num, row) in grid.iter().enumerate() {
-        let mut current_number_word: Option<String> = None;
-        for (column_num, cell) in row.iter().enumerate() {
-            if cell.is_digit(10) {
-                current_number_word =
-                    Some(current_number_word.unwrap_or(String::new()) + &cell.to_string());
-            } else {
-                if current_number_word.is_some() {
-                    let value = current_number_word.unwrap().parse::<u32>().unwrap();
-                    cartesian_numbers.push(CartesianNumber {
-                        value,
-                        row_num,
-                        column_num: (column_num - value.to_string().len()),
-                    });
-                    current_number_word = None;
-                }
-            }
+    let mut column_num = 0;
+    while column_num < row.len() {
+        let is_sign = allow_negative
+            && row[column_num] == '-'
+            && row.get(column_num + 1).is_some_and(|x| x.is_ascii_digit());
+        if !is_sign && !row[column_num].is_ascii_digit() {
+            column_num += 1;
+            continue;
         }
-        // check if there's a current word at the end of loop - end of line word edge case
-        if current_number_word.is_some() {
-            let value = current_number_word.unwrap().parse::<u32>().unwrap();
-            cartesian_numbers.push(CartesianNumber {
-                value,
-                row_num,
-                column_num: (row.len() - value.to_string().len()),
-            });
+        // the number runs from here to the next non-digit or the end of the row, which
+        // also takes care of numbers that finish on the last column
+        let start = column_num;
+        column_num += 1;
+        while column_num < row.len() && row[column_num].is_ascii_digit() {
+            column_num += 1;
         }
+        let raw: String = row[start..column_num].iter().collect();
+        cartesian_numbers.push(CartesianNumber {
+            value: raw.parse::<i64>().unwrap(),
+            raw,
+            row_num,
+            column_num: start,
+            width: column_num - start,
+        });
     }
     cartesian_numbers
 }
 
-
 impl CartesianNumber {
     fn first_row_to_search(&self) -> usize {
         if self.row_num > 0 {
@@ -87,7 +264,7 @@ impl CartesianNumber {
         }
     }
 
-    fn last_row_to_search(&self, grid: &Vec<Vec<char>>) -> usize {
+    fn last_row_to_search(&self, grid: &[Vec<char>]) -> usize {
         if self.row_num < grid.len() - 1 {
             if self.row_num + 2 > grid.len()
             {
@@ -107,8 +284,8 @@ impl CartesianNumber {
         }
     }
 
-    fn last_column_to_search(&self, row: &Vec<char>) -> usize {
-        let final_column_of_word = self.column_num + self.value.to_string().len() - 1;
+    fn last_column_to_search(&self, row: &[char]) -> usize {
+        let final_column_of_word = self.column_num + self.width - 1;
         if final_column_of_word < row.len() - 1 {
             final_column_of_word + 2
         } else {
@@ -119,30 +296,117 @@ impl CartesianNumber {
         }
     }
 
-
-    fn is_engine_part(&self, grid: &Vec<Vec<char>>) -> bool {
-        for row_num in self.first_row_to_search()..self.last_row_to_search(grid) {
-            let row = &grid[row_num];
+    fn adjacent_symbol_coords(
+        &self,
+        grid: &[Vec<char>],
+        symbols: &HashMap<(usize, usize), char>,
+    ) -> Vec<(usize, usize)> {
+        // checked against the symbol map rather than the grid, so that no number's sign
+        // (whether this one's or a neighbour's) is mistaken for a symbol
+        let mut coords: Vec<(usize, usize)> = Vec::new();
+        for (row_num, row) in grid
+            .iter()
+            .enumerate()
+            .take(self.last_row_to_search(grid))
+            .skip(self.first_row_to_search())
+        {
             for column_num in self.first_column_to_search()..self.last_column_to_search(row) {
-                let candidate = &row[column_num];
-                if !candidate.is_numeric() && candidate != &'.' {
-                    return true;
+                if symbols.contains_key(&(row_num, column_num)) {
+                    coords.push((row_num, column_num));
                 }
             }
         }
-        false
+        coords
     }
+}
 
-    fn adjacent_asterisk_coords(&self, grid: &Vec<Vec<char>>) -> Option<(usize, usize)> {
-        for row_num in self.first_row_to_search()..self.last_row_to_search(grid) {
-            let row = &grid[row_num];
-            for column_num in self.first_column_to_search()..self.last_column_to_search(row) {
-                let candidate = &row[column_num];
-                if candidate == &'*' {
-                    return Some((row_num, column_num));
-                }
+impl SymbolIndex {
+    fn new(grid: &[Vec<char>], cartesian_numbers: &[CartesianNumber]) -> SymbolIndex {
+        // a minus sign that belongs to a number isn't a symbol in its own right
+        let signs: Vec<(usize, usize)> = cartesian_numbers
+            .iter()
+            .filter(|x| x.raw.starts_with('-'))
+            .map(|x| (x.row_num, x.column_num))
+            .collect();
+        let symbols: HashMap<(usize, usize), char> = grid
+            .iter()
+            .enumerate()
+            .flat_map(|(row_num, row)| {
+                row.iter()
+                    .enumerate()
+                    .filter(|(_, cell)| is_symbol(cell))
+                    .map(move |(column_num, &cell)| ((row_num, column_num), cell))
+            })
+            .filter(|(coords, _)| !signs.contains(coords))
+            .collect();
+        let symbols_by_number: Vec<Vec<(usize, usize)>> = cartesian_numbers
+            .iter()
+            .map(|cartesian_number| cartesian_number.adjacent_symbol_coords(grid, &symbols))
+            .collect();
+        let mut numbers_by_symbol: HashMap<(usize, usize), Vec<usize>> = HashMap::new();
+        for (number_index, coords) in symbols_by_number.iter().enumerate() {
+            for &coord in coords {
+                numbers_by_symbol
+                    .entry(coord)
+                    .or_default()
+                    .push(number_index);
             }
         }
-        None
+        SymbolIndex {
+            symbols,
+            symbols_by_number,
+            numbers_by_symbol,
+        }
+    }
+
+    fn is_engine_part(&self, number_index: usize) -> bool {
+        !self.symbols_by_number[number_index].is_empty()
+    }
+
+    fn symbol_kinds(&self) -> Vec<char> {
+        let mut kinds: Vec<char> = self.symbols.values().copied().collect();
+        kinds.sort();
+        kinds.dedup();
+        kinds
+    }
+
+    fn symbols_of_kind(&self, kind: char) -> Vec<(usize, usize)> {
+        let mut coords: Vec<(usize, usize)> = self
+            .symbols
+            .iter()
+            .filter(|(_, &symbol)| symbol == kind)
+            .map(|(&coords, _)| coords)
+            .collect();
+        coords.sort();
+        coords
+    }
+
+    fn numbers_adjacent_to(&self, coords: &(usize, usize)) -> &[usize] {
+        self.numbers_by_symbol
+            .get(coords)
+            .map_or(&[], |numbers| numbers.as_slice())
+    }
+
+    fn gear_ratios(
+        &self,
+        cartesian_numbers: &[CartesianNumber],
+        kind: char,
+        arity: usize,
+    ) -> Vec<((usize, usize), i64)> {
+        self.symbols_of_kind(kind)
+            .into_iter()
+            .filter_map(|coords| {
+                let numbers = self.numbers_adjacent_to(&coords);
+                if numbers.len() == arity {
+                    let ratio = numbers
+                        .iter()
+                        .map(|&number_index| cartesian_numbers[number_index].value)
+                        .product::<i64>();
+                    Some((coords, ratio))
+                } else {
+                    None
+                }
+            })
+            .collect()
     }
 }
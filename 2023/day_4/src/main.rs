@@ -2,63 +2,114 @@ use std::collections::HashSet;
 use std::fs;
 
 const INPUT_FILE_NAME: &str = "input.txt";
+// show how many copies of each scratchie we end up with
+const PRINT_COPY_COUNTS: bool = false;
+
+struct Scratchcard {
+    id: u32,
+    winning_numbers: Vec<u32>,
+    held_numbers: Vec<u32>,
+}
 
 fn main() {
-    let game_results = get_game_results();
-    println!("Part 1: {}", part1(&game_results));
-    println!("Part 2: {}", part2(&game_results));
+    let cards = get_cards();
+    println!("Part 1: {}", part1(&cards));
+    println!("Part 2: {}", part2(&cards));
 }
 
-fn get_game_results() -> Vec<usize> {
+fn get_cards() -> Vec<Scratchcard> {
     fs::read_to_string(INPUT_FILE_NAME)
         .unwrap()
         .lines()
-        .map(|line| line.split_at(line.find("|").unwrap()))
-        .map(|(winning_numbers, held_numbers)| {
-            num_matching(
-                parse_numbers(&winning_numbers[9..]), // first nine chars id game
-                parse_numbers(&held_numbers[1..]),    // first character is a pipe
-            )
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(index, line)| {
+            parse_card(line)
+                .unwrap_or_else(|err| panic!("{} line {}: {}", INPUT_FILE_NAME, index + 1, err))
         })
         .collect()
 }
 
-fn parse_numbers(numbers: &str) -> HashSet<u32> {
+fn parse_card(line: &str) -> Result<Scratchcard, String> {
+    let (label, numbers) = line
+        .split_once(':')
+        .ok_or_else(|| format!("expected Card <id>: but got {:?}", line))?;
+    let id = label
+        .strip_prefix("Card")
+        .and_then(|id| id.trim().parse::<u32>().ok())
+        .ok_or_else(|| format!("{:?} is not a card id", label))?;
+    let (winning_numbers, held_numbers) = numbers
+        .split_once('|')
+        .ok_or_else(|| format!("card {} has no | between its numbers", id))?;
+    Ok(Scratchcard {
+        id,
+        winning_numbers: parse_numbers(winning_numbers, id, "winning")?,
+        held_numbers: parse_numbers(held_numbers, id, "held")?,
+    })
+}
+
+fn parse_numbers(numbers: &str, id: u32, kind: &str) -> Result<Vec<u32>, String> {
+    let mut seen: HashSet<u32> = HashSet::new();
     numbers
         .split_whitespace()
-        .map(|number| number.parse::<u32>().unwrap())
+        .map(|number| {
+            let number = number
+                .parse::<u32>()
+                .map_err(|_| format!("{:?} in card {} is not a number", number, id))?;
+            // a repeat would be counted once by the matching, so it's almost certainly a
+            // mistake in the input rather than something we should quietly ignore
+            if !seen.insert(number) {
+                return Err(format!(
+                    "card {} lists {} twice in its {} numbers",
+                    id, number, kind
+                ));
+            }
+            Ok(number)
+        })
         .collect()
 }
 
-fn num_matching(winning_numbers: HashSet<u32>, held_numbers: HashSet<u32>) -> usize {
-    winning_numbers.intersection(&held_numbers).count()
+impl Scratchcard {
+    fn num_matching(&self) -> usize {
+        let winning_numbers: HashSet<&u32> = self.winning_numbers.iter().collect();
+        self.held_numbers
+            .iter()
+            .filter(|number| winning_numbers.contains(number))
+            .count()
+    }
 }
 
-fn part1(game_results: &Vec<usize>) -> u32 {
-    game_results.iter().map(|game| score(*game)).sum()
+fn part1(cards: &[Scratchcard]) -> u32 {
+    cards.iter().map(|card| score(card.num_matching())).sum()
 }
 
-fn part2(game_results: &Vec<usize>) -> usize {
-    // the total scratchies is the number of copies + the original set of scratchies
-    game_results
-        .iter()
-        .enumerate()
-        .fold(0, |acc, (i, _game_result)| {
-            // the +1 here is just so we count the original scratchie as well as the copies it generates
-            acc + 1 + get_num_copies(i, &game_results)
-        })
+fn part2(cards: &[Scratchcard]) -> usize {
+    let game_results: Vec<usize> = cards.iter().map(|card| card.num_matching()).collect();
+    let copy_counts = get_copy_counts(&game_results);
+    if PRINT_COPY_COUNTS {
+        for ((card, game_result), copies) in cards.iter().zip(&game_results).zip(&copy_counts) {
+            println!(
+                "Card {}: {} matches, {} copies",
+                card.id, game_result, copies
+            );
+        }
+    }
+    copy_counts.iter().sum()
 }
 
-fn get_num_copies(game_index: usize, all_games: &Vec<usize>) -> usize {
-    let game_result = all_games[game_index];
-    // recursively gets all copies for a given scratchie - may be finding copies of copies, etc
-    (game_index + 1..game_index + game_result + 1).fold(game_result, |acc, x| {
-        if x >= all_games.len() {
-            // we don't copy scratchies that don't exist
-            return acc;
+fn get_copy_counts(game_results: &[usize]) -> Vec<usize> {
+    // a scratchie only ever wins copies of later scratchies, so by the time we reach one
+    // we already know how many copies of it we hold, and every one of those copies wins
+    // the same set of later scratchies. This counts the original scratchie as well.
+    let mut copy_counts = vec![1; game_results.len()];
+    for (game_index, &game_result) in game_results.iter().enumerate() {
+        // we don't copy scratchies that don't exist
+        let last_copied = (game_index + game_result).min(game_results.len() - 1);
+        for x in game_index + 1..=last_copied {
+            copy_counts[x] += copy_counts[game_index];
         }
-        acc + get_num_copies(x, all_games)
-    })
+    }
+    copy_counts
 }
 
 fn score(game_result: usize) -> u32 {
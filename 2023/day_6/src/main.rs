@@ -1,22 +1,119 @@
-const INPUT_PAIRS: [(u64, u64); 4] = [(53, 250), (91, 1330), (67, 1081), (68, 1025)];
+use std::fs;
 
-const PART_2_INPUT_PAIR: (u64, u64) = (53916768, 250133010811025);
+const INPUT_FILE_NAME: &str = "input.txt";
+
+// the brute force path enumerates every press time, which is tens of millions of
+// entries for part 2, so it is only run when we want to double check the closed form
+const VERIFY_WITH_BRUTE_FORCE: bool = false;
 
 fn main() {
-    let part_1_result: u64 = INPUT_PAIRS
+    let (times, distances) = get_data();
+    let part_1_result: u64 = get_part_1_races(&times, &distances)
         .into_iter()
-        .map(|(time, distance)| {
-            calculate_all_winning_button_press_durations(time, distance).len() as u64
-        })
+        .map(|(time, distance)| count_winning_button_press_durations(time, distance))
         .product();
     println!("Part 1 result: {}", part_1_result);
+    let (part_2_time, part_2_distance) = get_part_2_race(&times, &distances);
     println!(
         "Part 2 result: {}",
-        calculate_all_winning_button_press_durations(PART_2_INPUT_PAIR.0, PART_2_INPUT_PAIR.1)
-            .len()
+        count_winning_button_press_durations(part_2_time, part_2_distance)
     );
 }
 
+fn get_data() -> (Vec<String>, Vec<String>) {
+    let contents = fs::read_to_string(INPUT_FILE_NAME).unwrap();
+    let mut lines = contents.lines().filter(|line| !line.trim().is_empty());
+    let times = parse_line(lines.next().expect("missing Time: line"), "Time:");
+    let distances = parse_line(lines.next().expect("missing Distance: line"), "Distance:");
+    assert_eq!(
+        times.len(),
+        distances.len(),
+        "every race needs both a time and a distance"
+    );
+    (times, distances)
+}
+
+fn parse_line(line: &str, label: &str) -> Vec<String> {
+    // keep the columns as digit strings so that part 2 can kern them back together
+    let columns: Vec<String> = line
+        .strip_prefix(label)
+        .unwrap_or_else(|| panic!("expected line to start with {}: {}", label, line))
+        .split_whitespace()
+        .map(|x| x.to_owned())
+        .collect();
+    for column in &columns {
+        assert!(
+            column.chars().all(|c| c.is_ascii_digit()),
+            "not a number in {} line: {}",
+            label,
+            column
+        );
+    }
+    columns
+}
+
+fn get_part_1_races(times: &[String], distances: &[String]) -> Vec<(u64, u64)> {
+    times
+        .iter()
+        .zip(distances)
+        .map(|(time, distance)| (time.parse().unwrap(), distance.parse().unwrap()))
+        .collect()
+}
+
+fn get_part_2_race(times: &[String], distances: &[String]) -> (u64, u64) {
+    // the bad kerning means each line is really one number with spaces in it
+    (
+        times.concat().parse().unwrap(),
+        distances.concat().parse().unwrap(),
+    )
+}
+
+fn count_winning_button_press_durations(total_race_time: u64, record_distance: u64) -> u64 {
+    let count = count_winning_button_press_durations_closed_form(total_race_time, record_distance);
+    if VERIFY_WITH_BRUTE_FORCE {
+        let brute_force_count =
+            calculate_all_winning_button_press_durations(total_race_time, record_distance).len()
+                as u64;
+        assert_eq!(
+            count, brute_force_count,
+            "closed form disagrees with brute force for time {} and distance {}",
+            total_race_time, record_distance
+        );
+    }
+    count
+}
+
+fn count_winning_button_press_durations_closed_form(
+    total_race_time: u64,
+    record_distance: u64,
+) -> u64 {
+    // we win when t * (T - t) > D, i.e. when t lies strictly between the roots of
+    // t^2 - T*t + D = 0, which are (T +/- sqrt(T^2 - 4D)) / 2.  Everything is done in
+    // integers so that exact ties (where a root is a whole number) are not counted
+    let time = total_race_time as u128;
+    let distance = record_distance as u128;
+    let discriminant = match (time * time).checked_sub(4 * distance) {
+        Some(discriminant) if discriminant > 0 => discriminant,
+        // the best possible press time can only tie or lose
+        _ => return 0,
+    };
+    let wins = |press: u128| press * (time - press) > distance;
+    // the integer square root puts us within one of the lower root, so nudge the
+    // estimate until it is the first winning press time
+    let mut first_win = (time - discriminant.isqrt()) / 2;
+    while first_win > 0 && wins(first_win - 1) {
+        first_win -= 1;
+    }
+    while first_win <= time / 2 && !wins(first_win) {
+        first_win += 1;
+    }
+    if first_win > time / 2 {
+        return 0;
+    }
+    // the winning press times are symmetric about T / 2, so the last win is T - first_win
+    (time - 2 * first_win + 1) as u64
+}
+
 fn calculate_all_winning_button_press_durations(
     total_race_time: u64,
     record_distance: u64,
@@ -1,184 +1,679 @@
 extern crate itertools;
 use itertools::Itertools;
+use std::env;
 use std::fs;
+use std::path::Path;
 use std::time::Instant;
 
 const INPUT_FILE_NAME: &str = "input.txt";
+// every *.rules file in this directory is played as an extra variant of the game
+const VARIANTS_DIRECTORY: &str = "variants";
+// the brute force wildcard search tries every possible substitution, which is up to
+// 13^5 candidate hands per hand, so it is kept only as a cross-check of the fast path
+const USE_BRUTE_FORCE_WILDCARDS: bool = false;
+// the fallback rank packs one hex digit per card, so it can only hold this many
+const MAX_CARDS_OR_RANKS: usize = 16;
+// `cargo run -- --report` prints a table explaining why each hand ended up at its rank
+const REPORT_FLAG: &str = "--report";
+// `cargo run -- --csv` writes the same explanation to a csv file per rule set
+const CSV_FLAG: &str = "--csv";
 
-#[derive(PartialEq, PartialOrd, Ord, Eq, Debug)]
+#[derive(PartialEq, PartialOrd, Ord, Eq, Debug, Clone, Copy)]
 enum HandType {
     HighCard,
     OnePair,
     TwoPairs,
     ThreeOfAKind,
+    Straight,
+    Flush,
     FullHouse,
     FourOfAKind,
+    StraightFlush,
     FiveOfAKind,
 }
 
 #[derive(PartialEq, PartialOrd, Ord, Eq, Debug)]
 struct PokerHand {
     hand_type: HandType,
-    fallback_rank: u32,
+    fallback_rank: u64,
+    bid: u16,
+    cards: Vec<u16>,
+    // what each wildcard in the hand was treated as, in the order they appear
+    wildcard_substitutes: Vec<u16>,
+}
+
+struct RankingReportRow {
+    rank: u32,
+    cards: String,
+    hand_type: HandType,
+    wildcard_substitution: String,
+    tie_break_key: String,
+    bid: u16,
+    winnings: u32,
+}
+
+struct RuleSet {
+    name: String,
+    input_file_name: String,
+    hand_size: usize,
+    // every card that can appear in a hand, from weakest to strongest
+    card_order: Vec<char>,
+    wildcards: Vec<char>,
+    // when there are suits, each card in a hand is written as its rank then its suit
+    suits: Vec<char>,
+    straights: bool,
+    flushes: bool,
+}
+
+struct ParsedHand {
+    cards: String,
     bid: u16,
-    cards: [u16; 5],
 }
 
 fn main() {
     let start = Instant::now();
 
-    let mut hands = get_hands();
-    hands.sort();
-    // print out each hand as a string now that they are sorted to check the sort algorithm
-    // for hand in &hands {
-    //     println!("{:?}", hand);
-    // }
+    let mut print_report = false;
+    let mut write_csv = false;
+    for arg in env::args().skip(1) {
+        match arg.as_str() {
+            REPORT_FLAG => print_report = true,
+            CSV_FLAG => write_csv = true,
+            other => panic!(
+                "unknown option {}, expected {} or {}",
+                other, REPORT_FLAG, CSV_FLAG
+            ),
+        }
+    }
 
-    let scores: Vec<u32> = hands
-        .iter()
-        .enumerate()
-        .map(|(index, hand)| {
-            let rank = index as u32 + 1;
-            rank * hand.bid as u32
-        })
-        .collect();
+    let mut rule_sets = vec![RuleSet::part_1(), RuleSet::part_2()];
+    rule_sets.extend(get_variant_rule_sets());
 
-    // for score in &scores {
-    //     println!("Score: {}", score);
-    // }
+    let parsed_hands = get_hands(INPUT_FILE_NAME);
+    for rules in &rule_sets {
+        let variant_hands;
+        let parsed_hands = if rules.input_file_name == INPUT_FILE_NAME {
+            &parsed_hands
+        } else {
+            variant_hands = get_hands(&rules.input_file_name);
+            &variant_hands
+        };
+        let mut hands: Vec<PokerHand> = parsed_hands
+            .iter()
+            .map(|hand| PokerHand::new(hand, rules))
+            .collect();
+        hands.sort();
 
-    let sum_of_scores: u32 = scores.iter().sum();
-    println!("Sum of scores: {}", sum_of_scores);
+        let scores: Vec<u32> = hands
+            .iter()
+            .enumerate()
+            .map(|(index, hand)| {
+                let rank = index as u32 + 1;
+                rank * hand.bid as u32
+            })
+            .collect();
+
+        if print_report || write_csv {
+            let report = get_ranking_report(&hands, rules);
+            if print_report {
+                print_ranking_report(&report, rules);
+            }
+            if write_csv {
+                write_ranking_report_csv(&report, rules);
+            }
+        }
+
+        let sum_of_scores: u32 = scores.iter().sum();
+        println!("{} sum of scores: {}", rules.name, sum_of_scores);
+    }
     println!("Elapsed seconds: {}", start.elapsed().as_secs_f64());
 }
 
-fn get_hands() -> Vec<PokerHand> {
-    fs::read_to_string(INPUT_FILE_NAME)
+fn get_hands(file_name: &str) -> Vec<ParsedHand> {
+    fs::read_to_string(file_name)
         .unwrap()
         .lines()
-        .map(|x| get_hand_from_line(x))
+        .filter(|line| !line.trim().is_empty())
+        .map(get_hand_from_line)
+        .collect()
+}
+
+fn get_hand_from_line(line: &str) -> ParsedHand {
+    let (cards, bid) = line
+        .trim()
+        .split_once(' ')
+        .unwrap_or_else(|| panic!("expected cards then a bid: {}", line));
+    ParsedHand {
+        cards: cards.to_owned(),
+        bid: bid.trim().parse::<u16>().unwrap(),
+    }
+}
+
+fn get_variant_rule_sets() -> Vec<RuleSet> {
+    let Ok(entries) = fs::read_dir(VARIANTS_DIRECTORY) else {
+        return Vec::new();
+    };
+    entries
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| {
+            path.extension()
+                .is_some_and(|extension| extension == "rules")
+        })
+        .sorted()
+        .map(|path| RuleSet::from_file(&path))
         .collect()
 }
 
-fn get_hand_from_line(line: &str) -> PokerHand {
-    let (card_strings, bid) = line.split_at(6);
-    let card_strings = card_strings.trim();
-    let bid = bid.parse::<u16>().unwrap();
-    let cards: [u16; 5] = card_strings
-        .chars()
-        .map(|x| get_card_as_u16(&x))
-        .collect::<Vec<u16>>()
-        .try_into()
+impl RuleSet {
+    fn part_1() -> RuleSet {
+        RuleSet {
+            name: "Part 1".to_owned(),
+            input_file_name: INPUT_FILE_NAME.to_owned(),
+            hand_size: 5,
+            card_order: "23456789TJQKA".chars().collect(),
+            wildcards: Vec::new(),
+            suits: Vec::new(),
+            straights: false,
+            flushes: false,
+        }
+    }
+
+    fn part_2() -> RuleSet {
+        RuleSet {
+            name: "Part 2".to_owned(),
+            card_order: "J23456789TQKA".chars().collect(),
+            wildcards: vec!['J'],
+            ..RuleSet::part_1()
+        }
+    }
+
+    fn from_file(path: &Path) -> RuleSet {
+        // a rules file is made of "key = value" lines, and any key that is left out
+        // keeps its part 1 value, e.g.
+        //   name = Twos are wild too
+        //   input = input.txt
+        //   hand_size = 5
+        //   card_order = 2J3456789TQKA
+        //   wildcards = 2J
+        //   suits = cdhs
+        //   straights = true
+        //   flushes = true
+        let mut rules = RuleSet {
+            name: path.file_stem().unwrap().to_string_lossy().into_owned(),
+            ..RuleSet::part_1()
+        };
+        let contents = fs::read_to_string(path).unwrap();
+        for (line_number, line) in contents.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let (key, value) = line.split_once('=').unwrap_or_else(|| {
+                panic!("{:?} line {}: expected key = value", path, line_number + 1)
+            });
+            let value = value.trim();
+            let invalid = |expected: &str| -> ! {
+                panic!(
+                    "{:?} line {}: expected {} but got {}",
+                    path,
+                    line_number + 1,
+                    expected,
+                    value
+                )
+            };
+            match key.trim() {
+                "name" => rules.name = value.to_owned(),
+                "input" => rules.input_file_name = value.to_owned(),
+                "hand_size" => {
+                    rules.hand_size = value.parse().unwrap_or_else(|_| invalid("a hand size"))
+                }
+                "card_order" => rules.card_order = value.chars().collect(),
+                "wildcards" => rules.wildcards = value.chars().collect(),
+                "suits" => rules.suits = value.chars().collect(),
+                "straights" => {
+                    rules.straights = value.parse().unwrap_or_else(|_| invalid("true or false"))
+                }
+                "flushes" => {
+                    rules.flushes = value.parse().unwrap_or_else(|_| invalid("true or false"))
+                }
+                other => panic!(
+                    "{:?} line {}: unknown rule {}",
+                    path,
+                    line_number + 1,
+                    other
+                ),
+            }
+        }
+        rules.validate();
+        rules
+    }
+
+    fn validate(&self) {
+        assert!(
+            (1..=MAX_CARDS_OR_RANKS).contains(&self.hand_size),
+            "{}: hand size must be between 1 and {}",
+            self.name,
+            MAX_CARDS_OR_RANKS
+        );
+        assert!(
+            (1..=MAX_CARDS_OR_RANKS).contains(&self.card_order.len()),
+            "{}: there must be between 1 and {} ranks",
+            self.name,
+            MAX_CARDS_OR_RANKS
+        );
+        assert!(
+            self.card_order.iter().all_unique(),
+            "{}: card order repeats a card",
+            self.name
+        );
+        assert!(
+            self.wildcards
+                .iter()
+                .all(|card| self.card_order.contains(card)),
+            "{}: every wildcard must be in the card order",
+            self.name
+        );
+        assert!(
+            !self.flushes || !self.suits.is_empty(),
+            "{}: flushes need suits",
+            self.name
+        );
+    }
+
+    fn get_card_as_u16(&self, card: &char) -> u16 {
+        self.card_order
+            .iter()
+            .position(|x| x == card)
+            .unwrap_or_else(|| panic!("{} is not a card in {}", card, self.name)) as u16
+    }
+
+    fn num_ranks(&self) -> u16 {
+        self.card_order.len() as u16
+    }
+
+    fn is_wildcard(&self, card: u16) -> bool {
+        self.wildcards.contains(&self.card_order[card as usize])
+    }
+
+    fn get_cards_and_suits(&self, cards: &str) -> (Vec<u16>, Vec<char>) {
+        let chars: Vec<char> = cards.chars().collect();
+        let chars_per_card = if self.suits.is_empty() { 1 } else { 2 };
+        assert_eq!(
+            chars.len(),
+            self.hand_size * chars_per_card,
+            "{} is not a hand of {} cards in {}",
+            cards,
+            self.hand_size,
+            self.name
+        );
+        let ranks = chars
+            .iter()
+            .step_by(chars_per_card)
+            .map(|card| self.get_card_as_u16(card))
+            .collect();
+        let suits: Vec<char> = chars.iter().skip(1).step_by(2).copied().collect();
+        if self.suits.is_empty() {
+            return (ranks, Vec::new());
+        }
+        for suit in &suits {
+            assert!(
+                self.suits.contains(suit),
+                "{} is not a suit in {}",
+                suit,
+                self.name
+            );
+        }
+        (ranks, suits)
+    }
+}
+
+fn get_card_counts(cards: &[u16]) -> [u8; MAX_CARDS_OR_RANKS] {
+    cards
+        .iter()
+        .fold([0; MAX_CARDS_OR_RANKS], |mut counts, &card| {
+            counts[card as usize] += 1;
+            counts
+        })
+}
+
+fn get_hand_type_from_cards(cards: &[u16], suits: &[char], rules: &RuleSet) -> HandType {
+    get_best_hand_type(cards, suits, 0, rules).0
+}
+
+fn get_hand_type_from_cards_with_wildcard_upgrade(
+    cards: &[u16],
+    suits: &[char],
+    rules: &RuleSet,
+) -> (HandType, Vec<u16>) {
+    let (fixed_indices, wildcard_indices): (Vec<usize>, Vec<usize>) =
+        (0..cards.len()).partition(|&index| !rules.is_wildcard(cards[index]));
+    let fixed_cards: Vec<u16> = fixed_indices.iter().map(|&index| cards[index]).collect();
+    let fixed_suits: Vec<char> = fixed_indices
+        .iter()
+        .filter_map(|&index| suits.get(index).copied())
+        .collect();
+    get_best_hand_type(&fixed_cards, &fixed_suits, wildcard_indices.len(), rules)
+}
+
+fn get_best_hand_type(
+    fixed_cards: &[u16],
+    fixed_suits: &[char],
+    num_wildcards: usize,
+    rules: &RuleSet,
+) -> (HandType, Vec<u16>) {
+    // the best use of a wildcard for a group is always to copy the most common other
+    // card, since growing the biggest group beats every other group it could make.
+    // Ties go to the highest rank, which is also what a hand of only wildcards copies
+    let mut counts = get_card_counts(fixed_cards);
+    let most_common = (0..rules.num_ranks() as usize)
+        .max_by_key(|&card| (counts[card], card))
         .unwrap();
-    PokerHand::new(cards, bid, true)
+    counts[most_common] += num_wildcards as u8;
+    let group_hand_type = get_hand_type_from_counts(&counts);
+
+    // wildcards can stand in for any rank and suit, so straights and flushes only
+    // depend on whether the fixed cards could be part of one
+    let straight = rules.straights && can_make_straight(fixed_cards, rules);
+    let flush = rules.flushes && fixed_suits.iter().all_equal();
+    let run_hand_type = match (straight, flush) {
+        (true, true) => HandType::StraightFlush,
+        (true, false) => HandType::Straight,
+        (false, true) => HandType::Flush,
+        (false, false) => HandType::HighCard,
+    };
+    if straight && run_hand_type > group_hand_type {
+        (run_hand_type, get_straight_substitutes(fixed_cards, rules))
+    } else {
+        (
+            group_hand_type.max(run_hand_type),
+            vec![most_common as u16; num_wildcards],
+        )
+    }
 }
 
-fn get_card_as_u16(card: &char) -> u16 {
-    match card {
-        'A' => 14,
-        'K' => 13,
-        'Q' => 12,
-        'J' => 11,
-        'T' => 10,
-        _ => card.to_digit(10).unwrap() as u16,
+fn can_make_straight(fixed_cards: &[u16], rules: &RuleSet) -> bool {
+    if rules.card_order.len() < rules.hand_size || !fixed_cards.iter().all_unique() {
+        return false;
+    }
+    match fixed_cards.iter().minmax().into_option() {
+        Some((lowest, highest)) => ((highest - lowest) as usize) < rules.hand_size,
+        None => true,
     }
 }
 
-fn get_hand_type_from_cards(cards: &[u16; 5]) -> HandType {
-    let counts = cards.iter().fold([0; 15], |mut counts, &card| {
-        counts[card as usize] += 1;
-        counts
-    });
+fn get_straight_substitutes(fixed_cards: &[u16], rules: &RuleSet) -> Vec<u16> {
+    // fill the gaps of the highest straight that still contains every fixed card
+    let hand_size = rules.hand_size as u16;
+    let highest_start = rules.num_ranks() - hand_size;
+    let start = fixed_cards
+        .iter()
+        .min()
+        .map_or(highest_start, |&lowest| lowest.min(highest_start));
+    (start..start + hand_size)
+        .filter(|card| !fixed_cards.contains(card))
+        .collect()
+}
 
-    let pair_count = counts.iter().filter(|&&count| count == 2).count();
-    let three_of_a_kind = counts.iter().any(|&count| count == 3);
-    let four_of_a_kind = counts.iter().any(|&count| count == 4);
-    let five_of_a_kind = counts.iter().any(|&count| count == 5);
+fn get_hand_type_from_counts(counts: &[u8; MAX_CARDS_OR_RANKS]) -> HandType {
+    let mut group_sizes: Vec<u8> = counts.iter().copied().filter(|&count| count > 0).collect();
+    group_sizes.sort_unstable_by(|a, b| b.cmp(a));
+    let largest = group_sizes.first().copied().unwrap_or(0);
+    let second_largest = group_sizes.get(1).copied().unwrap_or(0);
 
-    match (pair_count, three_of_a_kind, four_of_a_kind, five_of_a_kind) {
-        (0, false, false, true) => HandType::FiveOfAKind,
-        (0, false, true, false) => HandType::FourOfAKind,
-        (1, true, false, false) => HandType::FullHouse, // Add FullHouse case
-        (0, true, false, false) => HandType::ThreeOfAKind,
-        (1, false, false, false) => HandType::OnePair,
-        (2, false, false, false) => HandType::TwoPairs,
-        (_, _, _, _) => HandType::HighCard,
+    match (largest, second_largest) {
+        (5.., _) => HandType::FiveOfAKind,
+        (4, _) => HandType::FourOfAKind,
+        (3, 2..) => HandType::FullHouse,
+        (3, _) => HandType::ThreeOfAKind,
+        (2, 2) => HandType::TwoPairs,
+        (2, _) => HandType::OnePair,
+        (_, _) => HandType::HighCard,
     }
 }
 
-fn get_hand_type_from_cards_considering_jacks_as_wild(cards: &[u16; 5]) -> HandType {
-    let mut max_hand_type = get_hand_type_from_cards(&cards);
+fn get_hand_type_from_cards_considering_wildcards(
+    cards: &[u16],
+    suits: &[char],
+    rules: &RuleSet,
+) -> (HandType, Vec<u16>) {
+    let mut max_hand_type = get_hand_type_from_cards(cards, suits, rules);
 
-    // Check if there are any jacks in the hand
-    if cards.contains(&11) {
-        let wild_jack_indices: Vec<usize> = cards
-            .iter()
-            .enumerate()
-            .filter(|(_, &card)| card == 11)
-            .map(|(index, _)| index)
-            .collect();
+    let wildcard_indices: Vec<usize> = cards
+        .iter()
+        .enumerate()
+        .filter(|(_, &card)| rules.is_wildcard(card))
+        .map(|(index, _)| index)
+        .collect();
+    let mut best_substitutes: Vec<u16> =
+        wildcard_indices.iter().map(|&index| cards[index]).collect();
+
+    // Check if there are any wildcards in the hand
+    if !wildcard_indices.is_empty() {
+        // a wildcard can only help a flush by matching the other cards, so rather than
+        // trying every suit it takes the suit of the first card that is not wild, or any
+        // one suit if every card is wild
+        let mut candidate_suits = suits.to_vec();
+        if let Some(&suit) = (0..cards.len())
+            .find(|index| !wildcard_indices.contains(index))
+            .and_then(|index| suits.get(index))
+            .or_else(|| suits.first())
+        {
+            for &index in &wildcard_indices {
+                candidate_suits[index] = suit;
+            }
+        }
 
         // Generate all possible permutations of wild card replacements
-        let permutations = generate_permutations(wild_jack_indices.len(), 14);
+        let permutations = generate_permutations(wildcard_indices.len(), rules.num_ranks());
 
         // Iterate through each permutation
         for permutation in permutations {
-            let mut candidate_cards = cards.clone();
+            let mut candidate_cards = cards.to_vec();
 
-            // Replace the wild jacks with the cards from the permutation
-            for (i, &index) in wild_jack_indices.iter().enumerate() {
+            // Replace the wildcards with the cards from the permutation
+            for (i, &index) in wildcard_indices.iter().enumerate() {
                 candidate_cards[index] = permutation[i];
             }
 
             // Get the hand type for the candidate cards
-            let candidate_hand_type = get_hand_type_from_cards(&candidate_cards);
+            let candidate_hand_type =
+                get_hand_type_from_cards(&candidate_cards, &candidate_suits, rules);
 
             // Update the max hand type if necessary
             if candidate_hand_type > max_hand_type {
                 max_hand_type = candidate_hand_type;
+                best_substitutes = permutation;
             }
         }
     }
 
-    max_hand_type
+    (max_hand_type, best_substitutes)
 }
 
-fn generate_permutations(length: usize, max_value: u16) -> Vec<Vec<u16>> {
+fn generate_permutations(length: usize, num_values: u16) -> Vec<Vec<u16>> {
     (1..=length)
-        .map(|_| 1..=max_value)
+        .map(|_| 0..num_values)
         .multi_cartesian_product()
         .collect()
 }
 
-fn get_fallback_rank_from_cards(cards: &[u16; 5], jacks_are_wild: bool) -> u32 {
+fn get_fallback_rank_from_cards(cards: &[u16]) -> u64 {
+    // wildcards are already placed in the rule set's card order
     let card_string = cards
         .iter()
-        .map(|&card| {
-            // if the card is 11, it is a jack, so it has value 0
-            if card == 11 && jacks_are_wild {
-                return format!("{:X}", 0);
-            }
-            format!("{:X}", card)
-        })
+        .map(|&card| format!("{:X}", card))
         .collect::<String>();
-    u32::from_str_radix(&card_string, 16).unwrap()
+    u64::from_str_radix(&card_string, 16).unwrap()
 }
 
 impl PokerHand {
-    fn new(cards: [u16; 5], bid: u16, jacks_are_wild: bool) -> PokerHand {
-        let hand_type = {
-            if jacks_are_wild {
-                get_hand_type_from_cards_considering_jacks_as_wild(&cards)
-            } else {
-                get_hand_type_from_cards(&cards)
-            }
+    fn new(hand: &ParsedHand, rules: &RuleSet) -> PokerHand {
+        let (cards, suits) = rules.get_cards_and_suits(&hand.cards);
+        let (hand_type, wildcard_substitutes) = if rules.wildcards.is_empty() {
+            (get_hand_type_from_cards(&cards, &suits, rules), Vec::new())
+        } else if USE_BRUTE_FORCE_WILDCARDS {
+            get_hand_type_from_cards_considering_wildcards(&cards, &suits, rules)
+        } else {
+            get_hand_type_from_cards_with_wildcard_upgrade(&cards, &suits, rules)
         };
 
         PokerHand {
-            hand_type: hand_type,
-            fallback_rank: get_fallback_rank_from_cards(&cards, jacks_are_wild),
-            bid,
+            hand_type,
+            fallback_rank: get_fallback_rank_from_cards(&cards),
+            bid: hand.bid,
             cards,
+            wildcard_substitutes,
         }
     }
 }
+
+fn get_ranking_report(sorted_hands: &[PokerHand], rules: &RuleSet) -> Vec<RankingReportRow> {
+    let card_names = |cards: &[u16]| -> String {
+        cards
+            .iter()
+            .map(|&card| rules.card_order[card as usize])
+            .collect()
+    };
+    sorted_hands
+        .iter()
+        .enumerate()
+        .map(|(index, hand)| {
+            let rank = index as u32 + 1;
+            let wildcards: Vec<u16> = hand
+                .cards
+                .iter()
+                .copied()
+                .filter(|&card| rules.is_wildcard(card))
+                .collect();
+            let wildcard_substitution = if wildcards.is_empty() {
+                String::new()
+            } else {
+                format!(
+                    "{}->{}",
+                    card_names(&wildcards),
+                    card_names(&hand.wildcard_substitutes)
+                )
+            };
+            RankingReportRow {
+                rank,
+                cards: card_names(&hand.cards),
+                hand_type: hand.hand_type,
+                wildcard_substitution,
+                // the fallback rank is one hex digit per card holding its place in the
+                // card order, so showing it in hex lines each digit up with its card
+                tie_break_key: format!("{:0width$X}", hand.fallback_rank, width = hand.cards.len()),
+                bid: hand.bid,
+                winnings: rank * hand.bid as u32,
+            }
+        })
+        .collect()
+}
+
+fn print_ranking_report(report: &[RankingReportRow], rules: &RuleSet) {
+    println!("{} ranking report:", rules.name);
+    println!(
+        "{:>6} {:<8} {:<14} {:<16} {:<8} {:>5} {:>9}",
+        "Rank", "Cards", "Hand type", "Wildcards", "Tie key", "Bid", "Winnings"
+    );
+    for row in report {
+        println!(
+            "{:>6} {:<8} {:<14} {:<16} {:<8} {:>5} {:>9}",
+            row.rank,
+            row.cards,
+            format!("{:?}", row.hand_type),
+            row.wildcard_substitution,
+            row.tie_break_key,
+            row.bid,
+            row.winnings
+        );
+    }
+}
+
+fn write_ranking_report_csv(report: &[RankingReportRow], rules: &RuleSet) {
+    let file_name = format!(
+        "ranking_report_{}.csv",
+        rules
+            .name
+            .to_lowercase()
+            .chars()
+            .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+            .collect::<String>()
+    );
+    let mut csv =
+        String::from("rank,cards,hand_type,wildcard_substitution,tie_break_key,bid,winnings\n");
+    for row in report {
+        csv.push_str(&format!(
+            "{},{},{:?},{},{},{},{}\n",
+            row.rank,
+            row.cards,
+            row.hand_type,
+            row.wildcard_substitution,
+            row.tie_break_key,
+            row.bid,
+            row.winnings
+        ));
+    }
+    fs::write(&file_name, csv).unwrap();
+    println!("Wrote {} ranking report to {}", rules.name, file_name);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn verify_wildcard_upgrade_against_brute_force(rules: &RuleSet) {
+        // every way of suiting a hand, or just the one unsuited way if there are no suits
+        let suit_combinations: Vec<Vec<char>> = if rules.suits.is_empty() {
+            vec![Vec::new()]
+        } else {
+            generate_permutations(rules.hand_size, rules.suits.len() as u16)
+                .into_iter()
+                .map(|x| x.iter().map(|&suit| rules.suits[suit as usize]).collect())
+                .collect()
+        };
+        let mut num_hands_checked: u32 = 0;
+        for cards in generate_permutations(rules.hand_size, rules.num_ranks()) {
+            for suits in &suit_combinations {
+                assert_eq!(
+                    get_hand_type_from_cards_with_wildcard_upgrade(&cards, suits, rules).0,
+                    get_hand_type_from_cards_considering_wildcards(&cards, suits, rules).0,
+                    "wildcard upgrade disagrees with brute force for {:?} {:?} in {}",
+                    cards,
+                    suits,
+                    rules.name
+                );
+                num_hands_checked += 1;
+            }
+        }
+        // make sure nothing was skipped, so that the check really did cover every hand
+        let num_hands =
+            (rules.num_ranks() as u32).pow(rules.hand_size as u32) * suit_combinations.len() as u32;
+        assert_eq!(num_hands_checked, num_hands);
+    }
+
+    // checks that the fast and brute force wildcard paths agree on every possible hand.
+    // the brute force makes these slow in a debug build, so run them with
+    // `cargo test --release`
+    #[test]
+    fn wildcard_upgrade_agrees_with_brute_force() {
+        verify_wildcard_upgrade_against_brute_force(&RuleSet::part_2());
+    }
+
+    // the same again with straights and flushes, on a smaller deck so that every suiting
+    // of every hand can be tried
+    #[test]
+    fn wildcard_upgrade_agrees_with_brute_force_with_suits() {
+        let rules = RuleSet {
+            name: "Suited".to_owned(),
+            hand_size: 4,
+            card_order: "2J3456".chars().collect(),
+            wildcards: vec!['2', 'J'],
+            suits: vec!['c', 'd', 'h'],
+            straights: true,
+            flushes: true,
+            ..RuleSet::part_1()
+        };
+        rules.validate();
+        verify_wildcard_upgrade_against_brute_force(&rules);
+    }
+}
@@ -1,138 +1,475 @@
+use num::integer::{ExtendedGcd, Integer};
 use std::collections::HashMap;
+use std::env;
 use std::fs;
 
 use std::time::Instant;
 
 const INPUT_FILE_NAME: &str = "input.txt";
-// const INSTRUCTIONS: &str = "LR";
-const INSTRUCTIONS: &str = "LLRRRLRLLRLRRLRLRLRRRLLRRLRRRLRRRLRRRLRRRLRRRLRRLRLLRRRLRRLLRLRLLLRRLRRLRLRLRLRRRLRLRRRLRRLLLRRRLLRRLLRRLLRRRLLLLRLRLRRRLRLRRRLRLLLRLRRLRRRLRRRLRRRLRRRLLRRLLLLRRLLRRLLRRLRLRRRLRRRLRRRLRRLRRRLRRLRRLRRLRLRRRLRRLRRRLRRRLRRLRLRRRLRRLLRLRRLRRRLRLRRLRRRLRRLRRLRRRLLRRRR";
+// the brute force walks every ghost in lockstep, which takes far too long on most real
+// inputs, so it is only used to double check the cycle based answer
+const VALIDATE_WITH_BRUTE_FORCE: bool = false;
+// how far the brute force looks when the cycle based answer is that there is no answer
+const BRUTE_FORCE_STEP_LIMIT: u64 = 1_000_000_000;
+// print the tail, cycle and Z offsets found for each ghost
+const PRINT_GHOST_CYCLES: bool = false;
+// past this many ways of lining up the ghosts' Z nodes we simulate instead
+const MAX_Z_OFFSET_COMBINATIONS: usize = 100_000;
+// `cargo run -- --dot` writes the network out as a graphviz file, to be drawn with e.g.
+// `dot -Tsvg network.dot -o network.svg`, and `cargo run -- --dot AAA` writes only the part
+// of the network that can be reached from AAA
+const DOT_FLAG: &str = "--dot";
+const DOT_FILE_NAME: &str = "network.dot";
+
+type Network = HashMap<String, (String, String)>;
+
+// the network with every node interned to a dense id, so that a step is an array lookup
+struct NodeGraph {
+    names: Vec<String>,
+    ids: HashMap<String, u32>,
+    // the left and right neighbours of each node, indexed by id
+    edges: Vec<[u32; 2]>,
+    is_end: Vec<bool>,
+}
+
+impl NodeGraph {
+    fn new(data: &Network) -> NodeGraph {
+        let mut names: Vec<String> = data.keys().cloned().collect();
+        names.sort();
+        let ids: HashMap<String, u32> = names
+            .iter()
+            .enumerate()
+            .map(|(id, name)| (name.clone(), id as u32))
+            .collect();
+        let edges = names
+            .iter()
+            .map(|name| {
+                let (left, right) = data.get(name).unwrap();
+                [ids[left], ids[right]]
+            })
+            .collect();
+        let is_end = names.iter().map(|name| name.ends_with('Z')).collect();
+        NodeGraph {
+            names,
+            ids,
+            edges,
+            is_end,
+        }
+    }
+
+    fn len(&self) -> usize {
+        self.names.len()
+    }
+
+    fn id(&self, name: &str) -> Option<u32> {
+        self.ids.get(name).copied()
+    }
+
+    fn name(&self, id: u32) -> &str {
+        &self.names[id as usize]
+    }
+
+    fn step(&self, id: u32, instruction: char) -> u32 {
+        let [left, right] = self.edges[id as usize];
+        if instruction == 'L' {
+            left
+        } else {
+            right
+        }
+    }
+
+    fn is_end(&self, id: u32) -> bool {
+        self.is_end[id as usize]
+    }
+
+    fn reachable_from(&self, start: u32) -> Vec<bool> {
+        let mut reachable = vec![false; self.len()];
+        let mut to_visit = vec![start];
+        while let Some(id) = to_visit.pop() {
+            if !reachable[id as usize] {
+                reachable[id as usize] = true;
+                to_visit.extend(self.edges[id as usize]);
+            }
+        }
+        reachable
+    }
+
+    fn starts(&self) -> Vec<u32> {
+        (0..self.len() as u32)
+            .filter(|&id| self.name(id).ends_with('A'))
+            .collect()
+    }
+}
 
 fn main() {
     let start = Instant::now();
-    let data = get_data();
-    part_1(&data);
-    part_2_fast_mode(&data);
+    let (instructions, data) = get_data();
+    let graph = NodeGraph::new(&data);
+    if let Some(reachable_from) = get_dot_option() {
+        write_dot_file(&graph, reachable_from.as_deref());
+    }
+    part_1(&instructions, &graph);
+    let num_moves = part_2(&instructions, &graph);
+    if VALIDATE_WITH_BRUTE_FORCE {
+        validate_part_2(&instructions, &graph, num_moves);
+    }
     println!("Elapsed seconds: {}", start.elapsed().as_secs_f64());
 }
 
-fn part_1(data: &HashMap<String, (String, String)>) {
-    let instructions: Vec<char> = INSTRUCTIONS.chars().collect();
-    let mut current_location = "AAA";
-    let mut num_moves: u32 = 0;
-    while current_location != "ZZZ" {
-        for instruction in &instructions {
-            let (left, right) = data.get(current_location).unwrap();
-            if instruction == &'L' {
-                current_location = left;
-            } else {
-                current_location = right;
-            }
-            num_moves += 1;
-            if current_location == "ZZZ" {
-                break;
-            }
+fn part_1(instructions: &[char], graph: &NodeGraph) {
+    let (Some(mut current_location), Some(destination)) = (graph.id("AAA"), graph.id("ZZZ")) else {
+        let missing: Vec<&str> = ["AAA", "ZZZ"]
+            .into_iter()
+            .filter(|name| graph.id(name).is_none())
+            .collect();
+        println!(
+            "Skipping part 1: {}: no node {}",
+            INPUT_FILE_NAME,
+            missing.join(" or ")
+        );
+        return;
+    };
+    // by then every (node, instruction) pair has come up, so the walk is going round in
+    // circles without ever reaching ZZZ
+    let step_limit = graph.len() as u64 * instructions.len() as u64;
+    let mut num_moves: u64 = 0;
+    for instruction in instructions.iter().cycle() {
+        if current_location == destination {
+            break;
+        }
+        if num_moves == step_limit {
+            println!(
+                "Skipping part 1: {}: ZZZ is never reached from AAA",
+                INPUT_FILE_NAME
+            );
+            return;
         }
+        current_location = graph.step(current_location, *instruction);
+        num_moves += 1;
     }
     println!("Number of moves: {}", num_moves);
 }
 
-fn part_2_fast_mode(data: &HashMap<String, (String, String)>) {
-    let starting_locations: Vec<&str> = data
-        .keys()
-        .filter(|x| x.ends_with("A"))
-        .map(|x| x.as_str())
+struct GhostCycle {
+    start: String,
+    // the ghost walks tail_length steps before its (node, instruction) states repeat
+    tail_length: u64,
+    cycle_length: u64,
+    // every step before the first repeat on which the ghost is on a Z node, in order
+    z_offsets: Vec<u64>,
+}
+
+impl GhostCycle {
+    fn is_on_z_at(&self, step: u64) -> bool {
+        let step = if step < self.tail_length + self.cycle_length {
+            step
+        } else {
+            self.tail_length + (step - self.tail_length) % self.cycle_length
+        };
+        self.z_offsets.binary_search(&step).is_ok()
+    }
+
+    fn cycle_z_offsets(&self) -> impl Iterator<Item = &u64> {
+        self.z_offsets
+            .iter()
+            .filter(|&&offset| offset >= self.tail_length)
+    }
+}
+
+fn part_2(instructions: &[char], graph: &NodeGraph) -> Option<u64> {
+    let cycles: Vec<GhostCycle> = graph
+        .starts()
+        .into_iter()
+        .map(|start| find_ghost_cycle(start, instructions, graph))
         .collect();
-    let mut total_moves_to_victory: Vec<u64> = Vec::new();
-    let instructions: Vec<char> = INSTRUCTIONS.chars().collect();
-
-    for starting_location in starting_locations {
-        let mut current_location = starting_location;
-        let mut num_moves: u64 = 0;
-        while !current_location.ends_with("Z") {
-            for instruction in &instructions {
-                let (left, right) = data.get(current_location).unwrap();
-                if instruction == &'L' {
-                    current_location = left;
-                } else {
-                    current_location = right;
-                }
-                num_moves += 1;
-                if current_location.ends_with("Z") {
-                    break;
-                }
-            }
-            if current_location.ends_with("Z") {
-                total_moves_to_victory.push(num_moves);
-                break;
-            }
+    if PRINT_GHOST_CYCLES {
+        for cycle in &cycles {
+            println!(
+                "Ghost from {}: tail length {}, cycle length {}, Z offsets {:?}",
+                cycle.start, cycle.tail_length, cycle.cycle_length, cycle.z_offsets
+            );
         }
     }
-    let lcm = multi_lcm(&total_moves_to_victory);
-    println!("LCM: {}", lcm);
+
+    let num_combinations = cycles.iter().fold(1_usize, |acc, cycle| {
+        acc.saturating_mul(cycle.cycle_z_offsets().count())
+    });
+    let num_moves = if num_combinations > MAX_Z_OFFSET_COMBINATIONS {
+        println!(
+            "{} combinations of Z offsets is too many to check, falling back to simulation",
+            num_combinations
+        );
+        part_2_brute_force(instructions, graph, get_joint_state_repeat_step(&cycles))
+    } else {
+        find_first_step_with_all_ghosts_on_z(&cycles)
+    };
+    match num_moves {
+        Some(num_moves) => println!("Number of moves: {}", num_moves),
+        None => println!("The ghosts are never all on Z nodes at the same time"),
+    }
+    num_moves
 }
 
-fn multi_lcm(numbers: &[u64]) -> u64 {
-    numbers
+fn get_joint_state_repeat_step(cycles: &[GhostCycle]) -> u64 {
+    // once every ghost is in its cycle, all of them together are back where they started
+    // after the lcm of the cycle lengths, so a common Z step has to come before then
+    let longest_tail = cycles.iter().map(|x| x.tail_length).max().unwrap_or(0);
+    cycles
         .iter()
-        .skip(1)
-        .fold(numbers[0], |acc, num| num::integer::lcm(acc, *num))
+        .try_fold(1_u64, |acc, cycle| {
+            (acc / acc.gcd(&cycle.cycle_length)).checked_mul(cycle.cycle_length)
+        })
+        .and_then(|period| period.checked_add(longest_tail))
+        .unwrap_or(u64::MAX)
 }
 
-fn part_2_brute_force(data: &HashMap<String, (String, String)>) {
-    let starting_locations: Vec<&str> = data
-        .keys()
-        .filter(|x| x.ends_with("A"))
-        .map(|x| x.as_str())
-        .collect();
-    let mut current_locations: Vec<&str> = starting_locations.clone();
-    let mut num_moves: u128 = 0;
-    let instructions: Vec<char> = INSTRUCTIONS.chars().collect();
-
+fn find_ghost_cycle(start: u32, instructions: &[char], graph: &NodeGraph) -> GhostCycle {
+    // the walk is fully determined by the node and where we are in the instructions,
+    // so the first time one of those states comes round again the ghost is in a loop
+    let mut first_seen: Vec<Option<u64>> = vec![None; graph.len() * instructions.len()];
+    let mut z_offsets: Vec<u64> = Vec::new();
+    let mut current_location = start;
+    let mut step: u64 = 0;
     loop {
-        for instruction in &instructions {
-            let mut new_locations: Vec<&str> = Vec::new();
-            // move every current location forward by 1 step
-            for location in &current_locations {
-                let (left, right) = data.get(*location).unwrap();
-                if instruction == &'L' {
-                    new_locations.push(left);
-                } else {
-                    new_locations.push(right);
-                }
-            }
-            num_moves += 1;
-            if all_locations_end_in_z(&new_locations) {
-                println!("Number of moves: {}", num_moves);
-                return;
-            }
-            current_locations = new_locations.clone();
-            if (num_moves % 10000000) == 0 {
-                println!("Progress: {}", num_moves);
+        let instruction_index = step as usize % instructions.len();
+        let state = current_location as usize * instructions.len() + instruction_index;
+        if let Some(tail_length) = first_seen[state] {
+            return GhostCycle {
+                start: graph.name(start).to_owned(),
+                tail_length,
+                cycle_length: step - tail_length,
+                z_offsets,
+            };
+        }
+        first_seen[state] = Some(step);
+        if graph.is_end(current_location) {
+            z_offsets.push(step);
+        }
+        current_location = graph.step(current_location, instructions[instruction_index]);
+        step += 1;
+    }
+}
+
+fn find_first_step_with_all_ghosts_on_z(cycles: &[GhostCycle]) -> Option<u64> {
+    // until every ghost has reached its cycle, a common step has to be one of the tail
+    // Z offsets of the ghost with the longest tail, so those can just be checked
+    let longest_tail = cycles.iter().max_by_key(|cycle| cycle.tail_length)?;
+    let all_in_cycle_from = longest_tail.tail_length;
+    if let Some(&step) = longest_tail
+        .z_offsets
+        .iter()
+        .take_while(|&&offset| offset < all_in_cycle_from)
+        .find(|&&offset| cycles.iter().all(|cycle| cycle.is_on_z_at(offset)))
+    {
+        return Some(step);
+    }
+
+    // after that each ghost is on a Z node exactly when the step is congruent to one of
+    // its cycle Z offsets, so solve every combination of those with the chinese
+    // remainder theorem, which does not need the cycle lengths to be coprime
+    let solutions = cycles
+        .iter()
+        .fold(vec![(0_i128, 1_i128)], |solutions, cycle| {
+            solutions
+                .iter()
+                .flat_map(|&solution| {
+                    cycle.cycle_z_offsets().filter_map(move |&offset| {
+                        combine_congruences(solution, (offset as i128, cycle.cycle_length as i128))
+                    })
+                })
+                .collect()
+        });
+    solutions
+        .into_iter()
+        .map(|(remainder, modulus)| {
+            // the smallest step at least all_in_cycle_from in this congruence class
+            let from = all_in_cycle_from as i128;
+            if remainder >= from {
+                remainder
+            } else {
+                remainder + (from - remainder + modulus - 1) / modulus * modulus
             }
+        })
+        .min()
+        .map(|step| step as u64)
+}
+
+fn combine_congruences(
+    (remainder_a, modulus_a): (i128, i128),
+    (remainder_b, modulus_b): (i128, i128),
+) -> Option<(i128, i128)> {
+    // x = remainder_a (mod modulus_a) and x = remainder_b (mod modulus_b)
+    let ExtendedGcd { gcd, x, .. } = modulus_a.extended_gcd(&modulus_b);
+    let difference = remainder_b - remainder_a;
+    if difference % gcd != 0 {
+        return None;
+    }
+    let modulus = modulus_a / gcd * modulus_b;
+    let multiplier = (difference / gcd * x).rem_euclid(modulus_b / gcd);
+    let remainder = (remainder_a + modulus_a * multiplier).rem_euclid(modulus);
+    Some((remainder, modulus))
+}
+
+fn validate_part_2(instructions: &[char], graph: &NodeGraph, num_moves: Option<u64>) {
+    let step_limit = num_moves.unwrap_or(BRUTE_FORCE_STEP_LIMIT);
+    let brute_force_num_moves = part_2_brute_force(instructions, graph, step_limit);
+    assert_eq!(
+        num_moves, brute_force_num_moves,
+        "brute force disagrees with the cycle based answer"
+    );
+    println!("Brute force agrees within {} moves", step_limit);
+}
+
+fn part_2_brute_force(instructions: &[char], graph: &NodeGraph, step_limit: u64) -> Option<u64> {
+    let mut current_locations: Vec<u32> = graph.starts();
+    let mut num_moves: u64 = 0;
+
+    for instruction in instructions.iter().cycle() {
+        if num_moves == step_limit {
+            return None;
+        }
+        // move every current location forward by 1 step
+        for location in current_locations.iter_mut() {
+            *location = graph.step(*location, *instruction);
+        }
+        num_moves += 1;
+        if current_locations
+            .iter()
+            .all(|&location| graph.is_end(location))
+        {
+            return Some(num_moves);
+        }
+        if num_moves.is_multiple_of(100000000) {
+            println!("Progress: {}", num_moves);
         }
     }
+    unreachable!("instructions are never empty")
 }
 
-fn all_locations_end_in_z(locations: &Vec<&str>) -> bool {
-    for location in locations {
-        if !location.ends_with("Z") {
-            return false;
+fn get_dot_option() -> Option<Option<String>> {
+    // None when no graph was asked for, otherwise the node to export from, if any
+    let args: Vec<String> = env::args().skip(1).collect();
+    match args.as_slice() {
+        [] => None,
+        [flag] if flag == DOT_FLAG => Some(None),
+        [flag, node] if flag == DOT_FLAG => Some(Some(node.clone())),
+        _ => panic!("expected no options, {} or {} <node>", DOT_FLAG, DOT_FLAG),
+    }
+}
+
+fn write_dot_file(graph: &NodeGraph, reachable_from: Option<&str>) {
+    let start = reachable_from.map(|name| {
+        graph
+            .id(name)
+            .unwrap_or_else(|| panic!("{} is not a node in the network", name))
+    });
+    fs::write(DOT_FILE_NAME, network_to_dot(graph, start)).unwrap();
+    println!("Wrote network to {}", DOT_FILE_NAME);
+}
+
+fn network_to_dot(graph: &NodeGraph, reachable_from: Option<u32>) -> String {
+    let included = match reachable_from {
+        Some(start) => graph.reachable_from(start),
+        None => vec![true; graph.len()],
+    };
+    let mut dot = String::from("digraph network {\n");
+    for id in (0..graph.len() as u32).filter(|&id| included[id as usize]) {
+        let name = graph.name(id);
+        let style = if name.ends_with('A') {
+            ", style=filled, fillcolor=palegreen"
+        } else if graph.is_end(id) {
+            ", shape=doublecircle, style=filled, fillcolor=lightcoral"
+        } else {
+            ""
+        };
+        dot.push_str(&format!(
+            "    \"{}\" [label=\"{}\"{}];\n",
+            name, name, style
+        ));
+    }
+    for id in (0..graph.len() as u32).filter(|&id| included[id as usize]) {
+        let [left, right] = graph.edges[id as usize];
+        // plenty of nodes go to the same place either way, so draw those as one edge
+        let edges = if left == right {
+            vec![(left, "L/R")]
+        } else {
+            vec![(left, "L"), (right, "R")]
+        };
+        for (target, label) in edges {
+            dot.push_str(&format!(
+                "    \"{}\" -> \"{}\" [label=\"{}\"];\n",
+                graph.name(id),
+                graph.name(target),
+                label
+            ));
         }
     }
-    true
+    dot.push_str("}\n");
+    dot
 }
 
-fn get_data() -> HashMap<String, (String, String)> {
-    fs::read_to_string(INPUT_FILE_NAME)
-        .unwrap()
-        .lines()
-        .skip(2)
-        .map(|x| parse_line(x))
-        .collect()
+fn get_data() -> (Vec<char>, Network) {
+    let contents = fs::read_to_string(INPUT_FILE_NAME).unwrap();
+    parse_input(&contents).unwrap_or_else(|err| panic!("{}: {}", INPUT_FILE_NAME, err))
 }
 
-fn parse_line(line: &str) -> (String, (String, String)) {
-    let (name, directions) = line.split_once(" = ").unwrap();
-    let (left, right) = directions[1..9].split_once(", ").unwrap();
-    (name.to_owned(), (left.to_owned(), right.to_owned()))
+fn parse_input(contents: &str) -> Result<(Vec<char>, Network), String> {
+    let mut lines = contents.lines().enumerate();
+    let instructions = match lines.next() {
+        Some((_, line)) => parse_instructions(line).map_err(|err| format!("line 1: {}", err))?,
+        None => return Err("file is empty".to_owned()),
+    };
+
+    let mut data: Network = HashMap::new();
+    for (index, line) in lines.filter(|(_, line)| !line.trim().is_empty()) {
+        let (name, directions) =
+            parse_line(line).map_err(|err| format!("line {}: {}", index + 1, err))?;
+        if data.insert(name.clone(), directions).is_some() {
+            return Err(format!(
+                "line {}: node {} is defined twice",
+                index + 1,
+                name
+            ));
+        }
+    }
+    if data.is_empty() {
+        return Err("no nodes after the instructions".to_owned());
+    }
+    for (name, (left, right)) in &data {
+        for target in [left, right] {
+            if !data.contains_key(target) {
+                return Err(format!("node {} leads to unknown node {}", name, target));
+            }
+        }
+    }
+    Ok((instructions, data))
+}
+
+fn parse_instructions(line: &str) -> Result<Vec<char>, String> {
+    let instructions: Vec<char> = line.trim().chars().collect();
+    if instructions.is_empty() {
+        return Err("expected a line of L/R instructions".to_owned());
+    }
+    match instructions.iter().find(|&&x| x != 'L' && x != 'R') {
+        Some(x) => Err(format!("instruction {:?} is not L or R", x)),
+        None => Ok(instructions),
+    }
+}
+
+fn parse_line(line: &str) -> Result<(String, (String, String)), String> {
+    let (name, directions) = line
+        .split_once(" = ")
+        .ok_or_else(|| format!("expected NAME = (LEFT, RIGHT) but got {:?}", line))?;
+    let (left, right) = directions
+        .trim()
+        .strip_prefix('(')
+        .and_then(|x| x.strip_suffix(')'))
+        .and_then(|x| x.split_once(", "))
+        .ok_or_else(|| format!("expected (LEFT, RIGHT) but got {:?}", directions))?;
+    for node in [name, left, right] {
+        if node.is_empty() || !node.chars().all(|x| x.is_ascii_alphanumeric()) {
+            return Err(format!("{:?} is not a valid node name", node));
+        }
+    }
+    Ok((name.to_owned(), (left.to_owned(), right.to_owned())))
 }
@@ -1,78 +1,451 @@
+use num::rational::Ratio;
+#[cfg(feature = "bigint")]
+use num::BigInt;
+use num::{CheckedAdd, CheckedDiv, CheckedMul, CheckedSub, Integer};
+use std::env;
+use std::fmt;
 use std::fs;
+use std::str::FromStr;
 
 const INPUT_FILE_NAME: &str = "input.txt";
+// cross-check every extrapolation against the full difference triangle
+const VERIFY_WITH_DIFFERENCE_TRIANGLE: bool = false;
+// print the polynomial each line was fitted with
+const PRINT_POLYNOMIALS: bool = false;
+// `cargo run -- --skip-bad-lines` leaves out lines that can't be extrapolated rather than
+// failing the whole part
+const SKIP_BAD_LINES_FLAG: &str = "--skip-bad-lines";
+// extrapolated cells in the difference triangle are drawn in bold green
+const HIGHLIGHT_START: &str = "\x1b[1;32m";
+const HIGHLIGHT_END: &str = "\x1b[0m";
+
+// lines are worked out in an i64 first and only widened to this when they overflow
+#[cfg(feature = "bigint")]
+type WidestNumber = BigInt;
+#[cfg(not(feature = "bigint"))]
+type WidestNumber = i128;
+
+trait SequenceNumber:
+    Clone
+    + fmt::Display
+    + FromStr
+    + Integer
+    + CheckedAdd
+    + CheckedSub
+    + CheckedMul
+    + CheckedDiv
+    + From<i64>
+{
+}
+
+impl<T> SequenceNumber for T where
+    T: Clone
+        + fmt::Display
+        + FromStr
+        + Integer
+        + CheckedAdd
+        + CheckedSub
+        + CheckedMul
+        + CheckedDiv
+        + From<i64>
+{
+}
+
+#[derive(Debug, PartialEq)]
+enum SequenceError {
+    NotANumber(String),
+    NotPolynomial(usize),
+    Overflow,
+}
+
+impl fmt::Display for SequenceError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SequenceError::NotANumber(x) => write!(f, "{:?} is not a number", x),
+            SequenceError::NotPolynomial(length) => write!(
+                f,
+                "{} values are not enough to show that the sequence is a polynomial",
+                length
+            ),
+            SequenceError::Overflow => write!(f, "the numbers got too big and overflowed"),
+        }
+    }
+}
+
+// a sequence written in Newton's forward difference form: the value at index x is
+// sum of leading_differences[k] * (x choose k), where x = 0 is the first element
+struct NewtonPolynomial<T> {
+    leading_differences: Vec<T>,
+    sequence_length: usize,
+}
+
 fn main() {
     let data = get_data();
-    part_1(&data);
-    part_2(&data);
+    // `cargo run -- 3 7` draws the difference triangles of lines 3 and 7 instead
+    let (flags, line_numbers): (Vec<String>, Vec<String>) =
+        env::args().skip(1).partition(|x| x.starts_with("--"));
+    let mut skip_bad_lines = false;
+    for flag in &flags {
+        match flag.as_str() {
+            SKIP_BAD_LINES_FLAG => skip_bad_lines = true,
+            other => panic!("unknown option {}, expected {}", other, SKIP_BAD_LINES_FLAG),
+        }
+    }
+    if !line_numbers.is_empty() {
+        for line_number in &line_numbers {
+            draw_line(&data, line_number);
+        }
+        return;
+    }
+    part_1(&data, skip_bad_lines);
+    part_2(&data, skip_bad_lines);
+    if PRINT_POLYNOMIALS {
+        print_polynomials(&data);
+    }
 }
 
-fn get_data() -> Vec<Vec<i32>> {
+fn get_data() -> Vec<String> {
     fs::read_to_string(INPUT_FILE_NAME)
         .unwrap()
         .lines()
-        .map(|x| parse_line(x))
+        .map(|x| x.to_owned())
         .collect()
 }
 
-fn part_1(data: &Vec<Vec<i32>>) {
-    let res: i32 = data
-        .iter()
-        .map(|line| {
-            let difference_vectors = get_all_difference_vectors(line);
-            calculate_next_value_from_difference_vectors(&difference_vectors)
-        })
-        .sum();
-    println!("Part 1: {}", res);
+fn part_1(data: &[String], skip_bad_lines: bool) {
+    match sum_of_extrapolations(data, 1, skip_bad_lines) {
+        Ok(res) => println!("Part 1: {}", res),
+        Err((line_number, err)) => println!("Part 1 failed on line {}: {}", line_number, err),
+    }
 }
 
-fn part_2(data: &Vec<Vec<i32>>) {
-    let res: i32 = data
+fn part_2(data: &[String], skip_bad_lines: bool) {
+    match sum_of_extrapolations(data, -1, skip_bad_lines) {
+        Ok(res) => println!("Part 2: {}", res),
+        Err((line_number, err)) => println!("Part 2 failed on line {}: {}", line_number, err),
+    }
+}
+
+fn sum_of_extrapolations(
+    data: &[String],
+    steps: i64,
+    skip_bad_lines: bool,
+) -> Result<WidestNumber, (usize, SequenceError)> {
+    // a sum that quietly leaves lines out would look just like the answer, so any line
+    // that can't be extrapolated fails the whole sum unless skipping was asked for
+    let mut total = WidestNumber::from(0);
+    for (index, line) in data.iter().enumerate() {
+        let value = match extrapolate_line(line, steps) {
+            Ok(value) => value,
+            Err(SequenceError::Overflow) => return Err((index + 1, SequenceError::Overflow)),
+            Err(err) if skip_bad_lines => {
+                println!("Skipping line {}: {}", index + 1, err);
+                continue;
+            }
+            Err(err) => return Err((index + 1, err)),
+        };
+        total =
+            CheckedAdd::checked_add(&total, &value).ok_or((index + 1, SequenceError::Overflow))?;
+    }
+    Ok(total)
+}
+
+fn extrapolate_line(line: &str, steps: i64) -> Result<WidestNumber, SequenceError> {
+    // most lines fit comfortably in an i64, so only widen the ones that overflow
+    match extrapolate_line_as::<i64>(line, steps) {
+        Err(SequenceError::Overflow) => {}
+        result => return result.map(WidestNumber::from),
+    }
+    #[cfg(feature = "bigint")]
+    match extrapolate_line_as::<i128>(line, steps) {
+        Err(SequenceError::Overflow) => {}
+        result => return result.map(WidestNumber::from),
+    }
+    extrapolate_line_as::<WidestNumber>(line, steps)
+}
+
+fn extrapolate_line_as<T: SequenceNumber>(line: &str, steps: i64) -> Result<T, SequenceError> {
+    let values: Vec<T> = parse_line(line)?;
+    let value = NewtonPolynomial::fit(&values)?.extrapolate(steps)?;
+    if VERIFY_WITH_DIFFERENCE_TRIANGLE {
+        verify_against_difference_triangle(&values, steps, &value)?;
+    }
+    Ok(value)
+}
+
+fn verify_against_difference_triangle<T: SequenceNumber>(
+    values: &[T],
+    steps: i64,
+    value: &T,
+) -> Result<(), SequenceError> {
+    let difference_vectors = get_all_difference_vectors(values)?;
+    let expected = match steps {
+        1 => calculate_next_value_from_difference_vectors(&difference_vectors)?,
+        -1 => calculate_previous_value_from_difference_vectors(&difference_vectors)?,
+        _ => return Ok(()),
+    };
+    assert!(
+        *value == expected,
+        "difference triangle gives {} rather than {} for {:?}",
+        expected,
+        value,
+        values
+            .iter()
+            .map(|x| x.to_string())
+            .collect::<Vec<String>>()
+    );
+    Ok(())
+}
+
+fn print_polynomials(data: &[String]) {
+    for (index, line) in data.iter().enumerate() {
+        let polynomial = parse_line::<WidestNumber>(line)
+            .and_then(|values| NewtonPolynomial::fit(&values))
+            .and_then(|polynomial| Ok((polynomial.degree(), polynomial.coefficients()?)));
+        match polynomial {
+            Ok((degree, coefficients)) => println!(
+                "Line {}: degree {}, coefficients {}",
+                index + 1,
+                degree,
+                coefficients
+                    .iter()
+                    .map(|x| x.to_string())
+                    .collect::<Vec<String>>()
+                    .join(", ")
+            ),
+            Err(err) => println!("Line {}: {}", index + 1, err),
+        }
+    }
+}
+
+fn draw_line(data: &[String], line_number: &str) {
+    let line = match line_number.parse::<usize>() {
+        Ok(number) if (1..=data.len()).contains(&number) => &data[number - 1],
+        _ => {
+            println!(
+                "{} is not a line number between 1 and {}",
+                line_number,
+                data.len()
+            );
+            return;
+        }
+    };
+    println!("Line {}:", line_number);
+    match draw_difference_triangle(line) {
+        Ok(triangle) => println!("{}", triangle),
+        Err(err) => println!("{}", err),
+    }
+}
+
+fn draw_difference_triangle(line: &str) -> Result<String, SequenceError> {
+    let values: Vec<WidestNumber> = parse_line(line)?;
+    // make sure the triangle really does end in zeros before extrapolating from it
+    NewtonPolynomial::fit(&values)?;
+    let mut difference_vectors = get_all_difference_vectors(&values)?;
+    // the row of zeros isn't kept, but the puzzle's pictures show it
+    let zeros_length = difference_vectors.last().unwrap().len().saturating_sub(1);
+    difference_vectors.push(vec![WidestNumber::from(0); zeros_length]);
+
+    // each row's extrapolated values only depend on the rows below it
+    let rows: Vec<(String, Vec<String>, String)> = (0..difference_vectors.len())
+        .map(|row| {
+            let below = &difference_vectors[row..];
+            Ok((
+                calculate_previous_value_from_difference_vectors(below)?.to_string(),
+                below[0].iter().map(|x| x.to_string()).collect(),
+                calculate_next_value_from_difference_vectors(below)?.to_string(),
+            ))
+        })
+        .collect::<Result<_, SequenceError>>()?;
+    let width = rows
         .iter()
-        .map(|line| {
-            let difference_vectors = get_all_difference_vectors(line);
-            calculate_previous_value_from_difference_vectors(&difference_vectors)
+        .flat_map(|(previous, values, next)| values.iter().chain([previous, next]))
+        .map(|x| x.len())
+        .max()
+        .unwrap_or(1);
+
+    let triangle = rows
+        .iter()
+        .enumerate()
+        .map(|(row, (previous, values, next))| {
+            let indent = " ".repeat(row * (width + 1) / 2);
+            let cells = values
+                .iter()
+                .map(|x| format!("{:^width$}", x, width = width))
+                .collect::<Vec<String>>()
+                .join(" ");
+            format!(
+                "{}{}{:^width$}{} {} {}{:^width$}{}",
+                indent,
+                HIGHLIGHT_START,
+                previous,
+                HIGHLIGHT_END,
+                cells,
+                HIGHLIGHT_START,
+                next,
+                HIGHLIGHT_END,
+                width = width
+            )
         })
-        .sum();
-    println!("Part 2: {}", res);
+        .collect::<Vec<String>>()
+        .join("\n");
+    Ok(triangle)
 }
 
-fn get_all_difference_vectors(line: &Vec<i32>) -> Vec<Vec<i32>> {
-    std::iter::successors(Some(line.clone()), |last: &Vec<i32>| {
-        let differences = calculate_differences(last);
-        if differences.iter().any(|x| *x != 0) {
-            Some(differences)
+impl<T: SequenceNumber> NewtonPolynomial<T> {
+    fn fit(line: &[T]) -> Result<NewtonPolynomial<T>, SequenceError> {
+        // the leading diagonal of the difference triangle is all that the Newton form
+        // needs, so the differences are taken in place and only their first entries kept
+        let mut differences: Vec<T> = line.to_vec();
+        let mut leading_differences: Vec<T> = Vec::new();
+        while !differences.is_empty() {
+            if differences.iter().all(|x| x.is_zero()) {
+                return Ok(NewtonPolynomial {
+                    leading_differences,
+                    sequence_length: line.len(),
+                });
+            }
+            leading_differences.push(differences[0].clone());
+            for i in 0..differences.len() - 1 {
+                differences[i] = differences[i + 1]
+                    .checked_sub(&differences[i])
+                    .ok_or(SequenceError::Overflow)?;
+            }
+            differences.pop();
+        }
+        // we ran out of differences before reaching a row of zeros, so any polynomial
+        // would fit and an extrapolation would just be a guess
+        Err(SequenceError::NotPolynomial(line.len()))
+    }
+
+    fn degree(&self) -> usize {
+        // a sequence of zeros is the zero polynomial, which we count as degree 0
+        self.leading_differences.len().saturating_sub(1)
+    }
+
+    fn value_at(&self, x: i64) -> Result<T, SequenceError> {
+        // (x choose k + 1) = (x choose k) * (x - k) / (k + 1), which divides exactly and
+        // also works for negative x, so we can go backwards as well as forwards
+        let mut binomial = T::one();
+        let mut value = T::zero();
+        for (k, difference) in self.leading_differences.iter().enumerate() {
+            let term = difference
+                .checked_mul(&binomial)
+                .ok_or(SequenceError::Overflow)?;
+            value = value.checked_add(&term).ok_or(SequenceError::Overflow)?;
+            // the binomial after the last difference is never used, and working it out
+            // anyway could overflow on a line whose answer fits
+            if k + 1 == self.leading_differences.len() {
+                break;
+            }
+            binomial = binomial
+                .checked_mul(&T::from(x - k as i64))
+                .and_then(|x| x.checked_div(&T::from(k as i64 + 1)))
+                .ok_or(SequenceError::Overflow)?;
+        }
+        Ok(value)
+    }
+
+    fn extrapolate(&self, steps: i64) -> Result<T, SequenceError> {
+        // positive steps go past the last value and negative steps go before the first
+        if steps >= 0 {
+            let x = (self.sequence_length as i64 - 1).checked_add(steps);
+            self.value_at(x.ok_or(SequenceError::Overflow)?)
         } else {
-            None
+            self.value_at(steps)
+        }
+    }
+
+    fn coefficients(&self) -> Result<Vec<Ratio<T>>, SequenceError> {
+        // expand each (x choose k) = x (x - 1) ... (x - k + 1) / k! into powers of x and
+        // add them up, giving c[0] + c[1] x + c[2] x^2 + ... with x = 0 the first element
+        let overflow = || SequenceError::Overflow;
+        let mut coefficients = vec![Ratio::from_integer(T::zero()); self.degree() + 1];
+        let mut falling_factorial: Vec<T> = vec![T::one()];
+        let mut factorial = T::one();
+        for (k, difference) in self.leading_differences.iter().enumerate() {
+            for (power, coefficient) in falling_factorial.iter().enumerate() {
+                let numerator = difference.checked_mul(coefficient).ok_or_else(overflow)?;
+                coefficients[power] = coefficients[power]
+                    .checked_add(&Ratio::new(numerator, factorial.clone()))
+                    .ok_or_else(overflow)?;
+            }
+            // multiply the falling factorial by (x - k) ready for the next term
+            let k = T::from(k as i64);
+            let mut next = vec![T::zero(); falling_factorial.len() + 1];
+            for (power, coefficient) in falling_factorial.iter().enumerate() {
+                next[power + 1] = next[power + 1]
+                    .checked_add(coefficient)
+                    .ok_or_else(overflow)?;
+                let shifted = coefficient.checked_mul(&k).ok_or_else(overflow)?;
+                next[power] = next[power].checked_sub(&shifted).ok_or_else(overflow)?;
+            }
+            falling_factorial = next;
+            factorial = factorial
+                .checked_mul(&(k + T::one()))
+                .ok_or_else(overflow)?;
+        }
+        Ok(coefficients)
+    }
+}
+
+fn get_all_difference_vectors<T: SequenceNumber>(line: &[T]) -> Result<Vec<Vec<T>>, SequenceError> {
+    let mut difference_vectors = vec![line.to_vec()];
+    loop {
+        let differences = calculate_differences(difference_vectors.last().unwrap())?;
+        if differences.iter().all(|x| x.is_zero()) {
+            return Ok(difference_vectors);
         }
-    })
-    .collect()
+        difference_vectors.push(differences);
+    }
 }
 
-fn calculate_differences(line: &Vec<i32>) -> Vec<i32> {
-    line.windows(2).map(|x| x[1] - x[0]).collect()
+fn calculate_differences<T: SequenceNumber>(line: &[T]) -> Result<Vec<T>, SequenceError> {
+    line.windows(2)
+        .map(|x| x[1].checked_sub(&x[0]).ok_or(SequenceError::Overflow))
+        .collect()
 }
 
-fn calculate_next_value_from_difference_vectors(difference_vectors: &Vec<Vec<i32>>) -> i32 {
+fn calculate_next_value_from_difference_vectors<T: SequenceNumber>(
+    difference_vectors: &[Vec<T>],
+) -> Result<T, SequenceError> {
     // we could rewrite this and the "previous_value" version to use successors and a fold,
     // but it's far more readable just using a mutable variable.
-    let mut next_value_for_current_line: i32 = 0;
+    let mut next_value_for_current_line = T::zero();
     for vec in difference_vectors.iter().rev() {
-        next_value_for_current_line += vec.last().unwrap();
+        next_value_for_current_line = next_value_for_current_line
+            .checked_add(vec.last().unwrap())
+            .ok_or(SequenceError::Overflow)?;
     }
-    next_value_for_current_line
+    Ok(next_value_for_current_line)
 }
 
-fn calculate_previous_value_from_difference_vectors(difference_vectors: &Vec<Vec<i32>>) -> i32 {
-    let mut previous_value_for_current_line: i32 = 0;
+fn calculate_previous_value_from_difference_vectors<T: SequenceNumber>(
+    difference_vectors: &[Vec<T>],
+) -> Result<T, SequenceError> {
+    let mut previous_value_for_current_line = T::zero();
     for vec in difference_vectors.iter().rev() {
-        previous_value_for_current_line = vec.first().unwrap() - previous_value_for_current_line;
+        previous_value_for_current_line = vec
+            .first()
+            .unwrap()
+            .checked_sub(&previous_value_for_current_line)
+            .ok_or(SequenceError::Overflow)?;
     }
-    previous_value_for_current_line
+    Ok(previous_value_for_current_line)
 }
 
-fn parse_line(line: &str) -> Vec<i32> {
+fn parse_line<T: SequenceNumber>(line: &str) -> Result<Vec<T>, SequenceError> {
     line.split_whitespace()
-        .map(|x| x.parse::<i32>().unwrap())
+        .map(|x| {
+            x.parse::<T>().map_err(|_| {
+                // a well formed number that fails to parse is just too big for T
+                let digits = x.strip_prefix('-').unwrap_or(x);
+                if !digits.is_empty() && digits.chars().all(|c| c.is_ascii_digit()) {
+                    SequenceError::Overflow
+                } else {
+                    SequenceError::NotANumber(x.to_owned())
+                }
+            })
+        })
         .collect()
 }